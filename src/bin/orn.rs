@@ -1,11 +1,22 @@
+use std::cell::RefCell;
 use std::cmp::PartialEq;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::{env, process};
 
 use clap::CommandFactory;
-use clap::{Parser, Subcommand};
-use orn_cli::const_values::get_constant_values;
-use orn_cli::file_manager::FileManager;
-use orn_cli::gen_const::gen_consts;
-use orn_cli::update_notifier::{check_latest_version, REGISTRY_URL};
+use clap::{Args, Parser, Subcommand};
+use orn_cli::config::OrnConfig;
+use orn_cli::const_values::{discover_constant_files, load_constant_values, ConstantValue, Preset};
+use orn_cli::core_error::CoreError;
+use orn_cli::diagnostics::{render_diagnostics, Diagnostic, Diagnostics, DiagnosticsFormat};
+use orn_cli::error_codes::{error_catalogue, render_catalogue, CatalogueFormat};
+use orn_cli::explain::{explain_constant, find_constants};
+use orn_cli::extract::{extract_definitions, Extraction};
+use orn_cli::file_manager::{BackupMode, FileManager, UNDO_JOURNAL};
+use orn_cli::gen_const::{generate, stale_constants};
+use orn_cli::package::{discover_packages, MovePackage};
+use orn_cli::update_notifier::UpdateNotifier;
 
 /// ORN.
 #[derive(Parser, Debug)]
@@ -16,6 +27,10 @@ struct Cli {
     command: Option<Commands>,
     #[clap(short, long)]
     version: bool,
+    /// Don't reach the registry to check for a newer version of orn.
+    /// The check can also be disabled with `ORN_NO_UPDATE_CHECK=1`
+    #[arg(long, global = true)]
+    offline: bool,
 }
 #[derive(Subcommand, Clone, Debug, PartialEq)]
 enum Commands {
@@ -23,53 +38,456 @@ enum Commands {
     Version,
     /// Update constant values in Move files
     UpdateConst {
-        /// File paths, can be used multiple times, accept glob patterns
-        #[arg(short, long = "path", default_value = "**/*.move")]
-        paths: Vec<String>,
+        #[command(flatten)]
+        source: SourceArgs,
+        /// Print a unified diff of the changes instead of writing them
+        #[arg(long)]
+        dry_run: bool,
+        /// Keep the original content of the updated files, as `.orig` copies by default
+        /// or in a journal `orn undo` restores
+        #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "orig")]
+        backup: Option<BackupMode>,
+    },
+    /// Restore the files changed by the last `orn update-const --backup journal`
+    Undo {
+        /// Journal written by the update
+        #[arg(long, default_value = UNDO_JOURNAL)]
+        journal: PathBuf,
+    },
+    /// Check that the constants in Move files are up to date, without modifying them
+    Check {
+        #[command(flatten)]
+        source: SourceArgs,
+    },
+    /// Extract constant getters and declarations from Move files into a constant table
+    Extract {
+        #[command(flatten)]
+        files: FileArgs,
+        /// Write the table to this file instead of the standard output
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// List the Move files the other commands work on
+    ListFiles {
+        #[command(flatten)]
+        files: FileArgs,
+    },
+    /// Show how a constant is derived and the Move files using it
+    Explain {
+        /// Name of the constant, e.g. `MAX_N_QUERIES` or `verifier_addr::fri_layer::MAX_N_QUERIES`
+        name: String,
+        #[command(flatten)]
+        files: FileArgs,
+        #[command(flatten)]
+        table: TableArgs,
+    },
+    /// Generate a catalogue of the error codes of the constant tables
+    Errors {
+        #[command(flatten)]
+        table: TableArgs,
+        #[arg(long, value_enum, default_value = "markdown")]
+        format: CatalogueFormat,
+        /// Write the catalogue to this file instead of the standard output
+        #[arg(short, long)]
+        output: Option<PathBuf>,
     },
 }
 
-#[tokio::main]
-async fn main() {
-    // Will notify users in one day intervals if an update is available
-    if !check_latest_version(
-        env!("CARGO_PKG_NAME"),
-        env!("CARGO_PKG_VERSION"),
-        REGISTRY_URL
-    ).unwrap() {
-        return;
+/// Move files and constant tables to work on.
+#[derive(Args, Clone, Debug, PartialEq)]
+struct SourceArgs {
+    #[command(flatten)]
+    files: FileArgs,
+    #[command(flatten)]
+    table: TableArgs,
+    /// Format of the summary of unused, missing and shadowed constants. In JSON, the summary
+    /// is the only output on the standard output, the rest goes to the standard error
+    #[arg(long, value_enum, default_value = "text")]
+    format: DiagnosticsFormat,
+    /// List the unused constants of the table instead of counting them
+    #[arg(long)]
+    verbose: bool,
+}
+
+/// Move files to work on.
+#[derive(Args, Clone, Debug, PartialEq)]
+struct FileArgs {
+    /// File paths, can be used multiple times, accept glob patterns
+    #[arg(short, long = "path", default_value = "**/*.move")]
+    paths: Vec<String>,
+    /// Files to leave out, can be used multiple times, accept glob patterns.
+    /// Files ignored by `.gitignore` or `.ornignore` and `build` directories are always left out
+    #[arg(short, long)]
+    exclude: Vec<String>,
+    /// Move package, or workspace of packages, whose `sources`, `tests` and `scripts`
+    /// directories are used instead of the paths. Each package uses its own constant tables
+    #[arg(long)]
+    package: Option<PathBuf>,
+}
+
+impl FileArgs {
+    /// Files to work on, grouped by Move package when `--package` is given.
+    fn load(&self) -> Result<Vec<(Option<MovePackage>, FileManager)>, CoreError> {
+        let Some(dir) = &self.package else {
+            let file_manager = FileManager::load_with_excludes(&self.paths, &self.exclude)?;
+            return Ok(vec![(None, file_manager)]);
+        };
+        let packages = discover_packages(dir)?;
+        if packages.is_empty() {
+            return Err(CoreError::NoPackage(dir.display().to_string()));
+        }
+        packages
+            .into_iter()
+            .map(|package| {
+                let file_manager = FileManager::load_package(&package, &self.exclude)?;
+                Ok((Some(package), file_manager))
+            })
+            .collect()
     }
+}
+
+/// Constant tables to load.
+#[derive(Args, Clone, Debug, PartialEq)]
+struct TableArgs {
+    /// Constant tables in TOML format, can be used multiple times.
+    /// Defaults to `orn.toml`/`constants.toml` next to the closest `Move.toml`
+    #[arg(short, long = "constants")]
+    constants: Vec<PathBuf>,
+    /// Built-in constant table loaded before the files above.
+    /// Used by default when no constant table is found
+    #[arg(long, value_enum)]
+    preset: Option<Preset>,
+}
 
+#[tokio::main]
+async fn main() {
     let args = Cli::parse();
 
     if args.version {
         println!(env!("APP_VERSION"));
         return;
     }
+    let Some(command) = args.command else {
+        Cli::command().print_help().unwrap();
+        return;
+    };
+
+    let config = OrnConfig::load().unwrap_or_else(|err| {
+        eprintln!("warning: {}", err);
+        OrnConfig::default()
+    });
+    // runs alongside the command, and at most once a day unless configured otherwise
+    let update_check = UpdateNotifier::from_config(
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION"),
+        &config.update_check,
+    )
+    .offline(args.offline)
+    .check();
+    let success = run(command).await;
+    update_check.notify();
+    if !success {
+        process::exit(1);
+    }
+}
 
-    match args.command {
-        Some(command) => match command {
-            Commands::Version => {
-                println!(env!("APP_VERSION"));
-                return;
+/// Runs `command`, returning whether it succeeded.
+async fn run(command: Commands) -> bool {
+    let result = match command {
+        Commands::Version => {
+            println!(env!("APP_VERSION"));
+            Ok(())
+        }
+        Commands::UpdateConst {
+            source,
+            dry_run,
+            backup,
+        } => update_const(source, dry_run, backup).await,
+        Commands::Undo { journal } => FileManager::undo(&journal).map(|outcome| {
+            for path in outcome.restored {
+                println!("{}: restored", path.display());
             }
-            Commands::UpdateConst { paths } => {
-                update_const(&paths).await;
-                return;
+            for path in outcome.skipped {
+                eprintln!(
+                    "{}: modified since the update, not restored",
+                    path.display()
+                );
             }
+        }),
+        Commands::Check { source } => match check(source).await {
+            Ok(up_to_date) => return up_to_date,
+            Err(err) => Err(err),
         },
-        None => {
-            Cli::command().print_help().unwrap();
-            return;
+        Commands::Extract { files, output } => extract(files, output).await,
+        Commands::ListFiles { files } => files.load().map(|packages| {
+            for (package, file_manager) in packages {
+                if let Some(package) = package {
+                    println!("# package {} ({})", package.name, package.root.display());
+                }
+                for path in file_manager.files() {
+                    println!("{}", path.display());
+                }
+            }
+        }),
+        Commands::Explain { name, files, table } => explain(name, files, table).await,
+        Commands::Errors {
+            table,
+            format,
+            output,
+        } => errors(table, format, output).await,
+    };
+    if let Err(err) = &result {
+        eprintln!("error: {}", err);
+    }
+    result.is_ok()
+}
+
+/// Loads the given constant tables, falling back to the ones next to the `Move.toml` closest
+/// to `dir` and then to the StarkNet verifier preset.
+fn load_table(table: &TableArgs, dir: &Path) -> Result<HashMap<String, ConstantValue>, CoreError> {
+    let mut constants = table.constants.clone();
+    let mut preset = table.preset;
+    if constants.is_empty() {
+        constants = discover_constant_files(dir);
+    }
+    if constants.is_empty() && preset.is_none() {
+        preset = Some(Preset::StarknetVerifier);
+    }
+    load_constant_values(preset, &constants)
+}
+
+/// Loads the constant tables of `package`, or of the current directory without a package.
+fn load_package_table(
+    table: &TableArgs,
+    package: Option<&MovePackage>,
+) -> Result<HashMap<String, ConstantValue>, CoreError> {
+    let Some(package) = package else {
+        return load_table(table, &env::current_dir()?);
+    };
+    load_table(table, &package.root)
+}
+
+/// Prints the summary of the diagnostics, on the standard error unless it is meant for tooling.
+fn print_diagnostics(mut report: Vec<Diagnostic>, format: DiagnosticsFormat, verbose: bool) {
+    // packages sharing a table report its unused constants once
+    let mut reported = vec![];
+    report.retain(|diagnostic| {
+        let is_new = !reported.contains(diagnostic);
+        if is_new {
+            reported.push(diagnostic.clone());
         }
+        is_new
+    });
+    let summary = render_diagnostics(&report, format, verbose);
+    match format {
+        DiagnosticsFormat::Text => eprint!("{}", summary),
+        DiagnosticsFormat::Json => print!("{}", summary),
     }
 }
 
-async fn update_const(paths: &Vec<String>) {
-    let constant_values = get_constant_values();
+/// Prints output meant for people, on the standard error when the standard output is
+/// reserved for the JSON diagnostics.
+fn print_human(format: DiagnosticsFormat, text: &str) {
+    match format {
+        DiagnosticsFormat::Text => print!("{}", text),
+        DiagnosticsFormat::Json => eprint!("{}", text),
+    }
+}
+
+/// Updates the files of every package, writing them once all of them are processed so that a
+/// single journal covers the whole run.
+async fn update_const(
+    source: SourceArgs,
+    dry_run: bool,
+    backup: Option<BackupMode>,
+) -> Result<(), CoreError> {
+    let mut report = vec![];
+    let mut changes = vec![];
+    for (package, file_manager) in source.files.load()? {
+        let constant_values = load_package_table(&source.table, package.as_ref())?;
+        let diagnostics = RefCell::new(Diagnostics::default());
+        changes.extend(file_manager.process(|file_name, file_content| {
+            let (content, findings) = generate(&file_content, &constant_values)?;
+            diagnostics.borrow_mut().add(file_name, findings);
+            Ok(content)
+        })?);
+        report.extend(diagnostics.borrow().report(&constant_values));
+        if let Some(package) = &package {
+            report.extend(
+                diagnostics
+                    .borrow()
+                    .undeclared_addresses(&constant_values, package),
+            );
+        }
+    }
+    let changed: Vec<_> = changes.iter().filter(|c| c.is_changed()).collect();
+    if dry_run {
+        for change in &changed {
+            print_human(source.format, &change.unified_diff());
+        }
+        eprintln!(
+            "{} of {} files would be updated (dry run, nothing written)",
+            changed.len(),
+            changes.len()
+        );
+    } else {
+        FileManager::write_changes(&changes, backup, Path::new(UNDO_JOURNAL))?;
+        for change in changed {
+            print_human(source.format, &format!("{:?}: updated\n", change.file_name));
+        }
+    }
+    print_diagnostics(report, source.format, source.verbose);
+    Ok(())
+}
+
+/// Returns whether every file is up to date, listing the ones that are not.
+async fn check(source: SourceArgs) -> Result<bool, CoreError> {
+    let mut report = vec![];
+    let (mut files, mut outdated) = (0, 0);
+    for (package, file_manager) in source.files.load()? {
+        let constant_values = load_package_table(&source.table, package.as_ref())?;
+        let diagnostics = RefCell::new(Diagnostics::default());
+        let changes = file_manager.process(|file_name, file_content| {
+            let (content, findings) = generate(&file_content, &constant_values)?;
+            diagnostics.borrow_mut().add(file_name, findings);
+            Ok(content)
+        })?;
+        report.extend(diagnostics.borrow().report(&constant_values));
+        if let Some(package) = &package {
+            report.extend(
+                diagnostics
+                    .borrow()
+                    .undeclared_addresses(&constant_values, package),
+            );
+        }
+
+        files += changes.len();
+        for change in changes.iter().filter(|change| change.is_changed()) {
+            outdated += 1;
+            print_human(
+                source.format,
+                &format!(
+                    "{}: not up to date ({})\n",
+                    change.file_name,
+                    stale_constants(change, &constant_values).join(", ")
+                ),
+            );
+        }
+    }
+    print_diagnostics(report, source.format, source.verbose);
+
+    if outdated == 0 {
+        print_human(source.format, &format!("{} files are up to date\n", files));
+        return Ok(true);
+    }
+    print_human(
+        source.format,
+        &format!(
+            "{} of {} files are not up to date, run `orn update-const` to update them\n",
+            outdated, files
+        ),
+    );
+    Ok(false)
+}
+
+/// Prints the constant table extracted from the Move files, reporting duplicated and
+/// conflicting definitions on the standard error.
+async fn extract(files: FileArgs, output: Option<PathBuf>) -> Result<(), CoreError> {
+    let mut extraction = Extraction::default();
+    let mut contents = vec![];
+    for (_, file_manager) in files.load()? {
+        contents.extend(file_manager.read()?);
+    }
+    for (file_name, content) in contents {
+        let definitions =
+            extract_definitions(&content, &file_name).map_err(|err| CoreError::FileError {
+                path: file_name.clone(),
+                err: Box::new(err),
+            })?;
+        for definition in definitions {
+            extraction.add(definition);
+        }
+    }
+    for (name, definitions) in &extraction.duplicates {
+        let locations: Vec<_> = definitions.iter().map(|d| d.location()).collect();
+        eprintln!("duplicate: {} is defined in {}", name, locations.join(", "));
+    }
+    for (name, definitions) in &extraction.conflicts {
+        eprintln!("conflict: {} has different definitions:", name);
+        for definition in definitions {
+            eprintln!(
+                "    {}: {} = {}",
+                definition.location(),
+                definition.r#type,
+                definition.value
+            );
+        }
+    }
 
-    let file_manager = FileManager::load(paths).unwrap();
-    file_manager
-        .update(|file_content| gen_consts(&file_content, &constant_values))
-        .unwrap()
+    let table = extraction.to_toml();
+    match output {
+        Some(path) => std::fs::write(path, table)?,
+        None => print!("{}", table),
+    }
+    eprintln!(
+        "{} constants extracted, {} duplicated, {} conflicting",
+        extraction.constants.len(),
+        extraction.duplicates.len(),
+        extraction.conflicts.len()
+    );
+    Ok(())
+}
+
+/// Prints how the constants named `name` are derived, in every package defining one.
+async fn explain(name: String, files: FileArgs, table: TableArgs) -> Result<(), CoreError> {
+    let mut explained = false;
+    for (package, file_manager) in files.load()? {
+        let constant_values = load_package_table(&table, package.as_ref())?;
+        let keys = find_constants(&constant_values, &name);
+        if keys.is_empty() {
+            continue;
+        }
+        let mut uses = HashMap::<String, Vec<String>>::new();
+        for (file_name, content) in file_manager.read()? {
+            let (_, findings) =
+                generate(&content, &constant_values).map_err(|err| CoreError::FileError {
+                    path: file_name.clone(),
+                    err: Box::new(err),
+                })?;
+            for (module, key) in findings.uses {
+                uses.entry(key)
+                    .or_default()
+                    .push(format!("{} ({})", file_name, module));
+            }
+        }
+        if let Some(package) = package {
+            println!("# package {} ({})", package.name, package.root.display());
+        }
+        for key in keys {
+            if explained {
+                println!();
+            }
+            let uses = uses.get(key).map_or(&[][..], |uses| uses);
+            print!("{}", explain_constant(&constant_values, key, uses));
+            explained = true;
+        }
+    }
+    if !explained {
+        return Err(CoreError::UnknownConstant(name));
+    }
+    Ok(())
+}
+
+async fn errors(
+    table: TableArgs,
+    format: CatalogueFormat,
+    output: Option<PathBuf>,
+) -> Result<(), CoreError> {
+    let constant_values = load_table(&table, &env::current_dir()?)?;
+    let catalogue = render_catalogue(&error_catalogue(&constant_values), format);
+    match output {
+        Some(path) => std::fs::write(path, catalogue)?,
+        None => print!("{}", catalogue),
+    }
+    Ok(())
 }
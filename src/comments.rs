@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+
+use num_bigint::BigUint;
+
+use crate::const_values::{resolve_reference, ConstantValue};
+use crate::expression::Expression;
+
+/// The value as written in the table, e.g. `32 * MAX_COSET_SIZE`.
+pub const EXPRESSION_PLACEHOLDER: &str = "{expression}";
+/// The value with every constant it references replaced by their own expanded value.
+pub const EXPANDED_PLACEHOLDER: &str = "{expanded}";
+/// The value in decimal, e.g. `512`.
+pub const DECIMAL_PLACEHOLDER: &str = "{decimal}";
+/// The value in hexadecimal, e.g. `0x200`.
+pub const HEX_PLACEHOLDER: &str = "{hex}";
+
+/// What the placeholders of a comment template are replaced with.
+#[derive(Debug, Clone)]
+pub struct CommentValues<'a> {
+    pub expression: &'a str,
+    pub expanded: String,
+    /// The value of an integer, or the elements of a vector of integers
+    pub values: &'a [BigUint],
+    pub is_vector: bool,
+}
+
+impl CommentValues<'_> {
+    fn render_values(&self, render: impl Fn(&BigUint) -> String) -> String {
+        let values: Vec<_> = self.values.iter().map(render).collect();
+        match self.is_vector {
+            true => format!("[{}]", values.join(", ")),
+            false => values.join(", "),
+        }
+    }
+}
+
+/// Template of the comment of a constant that has none: its expression when it is derived
+/// from other constants or functions, nothing for literals.
+pub fn default_template(expressions: &[Expression]) -> Option<&'static str> {
+    expressions
+        .iter()
+        .any(|expression| !matches!(expression, Expression::Number(_)))
+        .then_some(EXPRESSION_PLACEHOLDER)
+}
+
+/// Replaces the placeholders of `template`, failing on unknown ones and on value placeholders
+/// for constants that are not integers.
+pub fn render_comment(template: &str, values: &CommentValues) -> Result<String, String> {
+    let mut result = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}').map(|end| start + end + 1) else {
+            break;
+        };
+        result += &rest[..start];
+        let placeholder = &rest[start..end];
+        let needs_value = placeholder == DECIMAL_PLACEHOLDER || placeholder == HEX_PLACEHOLDER;
+        if needs_value && values.values.is_empty() && !values.is_vector {
+            return Err(format!(
+                "the comment placeholder {} needs an integer value",
+                placeholder
+            ));
+        }
+        result += &match placeholder {
+            EXPRESSION_PLACEHOLDER => values.expression.to_string(),
+            EXPANDED_PLACEHOLDER => values.expanded.clone(),
+            DECIMAL_PLACEHOLDER => values.render_values(|value| value.to_string()),
+            HEX_PLACEHOLDER => values.render_values(|value| format!("0x{:x}", value)),
+            _ => return Err(format!("unknown comment placeholder {}", placeholder)),
+        };
+        rest = &rest[end..];
+    }
+    result += rest;
+    Ok(result)
+}
+
+/// A comment that is a number or an expression evaluating to another value than its constant.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StaleComment {
+    pub key: String,
+    pub comment: String,
+    /// Value of the comment
+    pub stated: BigUint,
+    pub value: BigUint,
+}
+
+/// Comments of the integer constants of `table` that are an expression, such as `48` or
+/// `2 ** MAX_STEP`, evaluating to another value than the constant. Other comments are left
+/// alone: prose, expressions referring to something else than the table, and formulas using
+/// `^`, which reads as a power in a comment but is a xor in the table.
+pub fn stale_comments(table: &HashMap<String, ConstantValue>) -> Vec<StaleComment> {
+    let mut stale = vec![];
+    for (key, constant) in table {
+        let (Some(comment), [value]) = (&constant.comment, constant.resolved.as_slice()) else {
+            continue;
+        };
+        if constant.error_message.is_some() {
+            continue;
+        }
+        let (Some(bits), false) = (constant.r#type.bits(), comment.contains('^')) else {
+            continue;
+        };
+        let Ok(expression) = Expression::parse(comment) else {
+            continue;
+        };
+        let lookup = |name: &str| {
+            let reference = resolve_reference(table, key, name)?;
+            table[reference].resolved.first().cloned()
+        };
+        let modulus = constant.field.as_ref().and_then(|field| {
+            table[resolve_reference(table, key, field)?]
+                .resolved
+                .first()
+        });
+        let stated = match modulus {
+            Some(modulus) => expression.evaluate_in_field(modulus, &lookup),
+            None => expression.evaluate(bits, &lookup),
+        };
+        let Ok(stated) = stated else {
+            continue;
+        };
+        if stated != *value {
+            stale.push(StaleComment {
+                key: key.clone(),
+                comment: comment.clone(),
+                stated,
+                value: value.clone(),
+            });
+        }
+    }
+    stale.sort_by(|a, b| a.key.cmp(&b.key));
+    stale
+}
+
+#[cfg(test)]
+mod test {
+    use num_bigint::BigUint;
+
+    use crate::comments::{default_template, render_comment, CommentValues};
+    use crate::expression::Expression;
+
+    #[test]
+    fn test_render_comment() {
+        let values = [BigUint::from(512u16)];
+        let comment = CommentValues {
+            expression: "32 * MAX_COSET_SIZE",
+            expanded: "32 * (1 << 4)".to_string(),
+            values: &values,
+            is_vector: false,
+        };
+        assert_eq!(
+            render_comment("{expression} = {expanded} = {decimal} ({hex})", &comment),
+            Ok("32 * MAX_COSET_SIZE = 32 * (1 << 4) = 512 (0x200)".to_string())
+        );
+        assert_eq!(
+            render_comment("Size {of} the group", &comment),
+            Err("unknown comment placeholder {of}".to_string())
+        );
+        assert_eq!(render_comment("a { b", &comment), Ok("a { b".to_string()));
+        let comment = CommentValues {
+            values: &[],
+            ..comment
+        };
+        assert!(render_comment("{decimal}", &comment).is_err());
+
+        let parse = |input| vec![Expression::parse(input).unwrap()];
+        assert_eq!(default_template(&parse("0x20")), None);
+        assert_eq!(
+            default_template(&parse("0x20 * MAX_COSET_SIZE")),
+            Some("{expression}")
+        );
+    }
+}
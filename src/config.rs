@@ -0,0 +1,88 @@
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+use serde::Deserialize;
+
+use crate::core_error::CoreError;
+use crate::update_notifier::UpdateCheckConfig;
+
+/// Path of the orn config, overriding the default location.
+pub const CONFIG_ENV: &str = "ORN_CONFIG";
+
+/// Settings of orn itself, as opposed to the constant tables of a project:
+///
+/// ```toml
+/// [update-check]
+/// interval = 12
+/// source = { type = "mirror", location = "https://mirror.example.com/orn-cli.json" }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct OrnConfig {
+    #[serde(default)]
+    pub update_check: UpdateCheckConfig,
+}
+
+/// `$ORN_CONFIG`, `$XDG_CONFIG_HOME/orn/config.toml` or `~/.config/orn/config.toml`.
+pub fn config_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os(CONFIG_ENV).filter(|path| !path.is_empty()) {
+        return Some(PathBuf::from(path));
+    }
+    let config_dir = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_dir.join("orn").join("config.toml"))
+}
+
+impl OrnConfig {
+    /// The config at [`config_path`], the default one if there is no such file.
+    pub fn load() -> Result<Self, CoreError> {
+        match config_path() {
+            Some(path) if path.is_file() => Self::from_file(&path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, CoreError> {
+        toml::from_str(&fs::read_to_string(path)?).map_err(|err| CoreError::InvalidConfig {
+            path: path.display().to_string(),
+            err: Box::new(err),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use crate::config::OrnConfig;
+    use crate::core_error::CoreError;
+    use crate::update_notifier::SourceConfig;
+
+    #[test]
+    fn test_load_config() {
+        let path = std::env::temp_dir().join("orn_test_config.toml");
+        fs::write(
+            &path,
+            "[update-check]\nprerelease = true\n\
+             source = { type = \"crates-io\", url = \"https://registry.example.com\" }\n",
+        )
+        .unwrap();
+        let config = OrnConfig::from_file(&path).unwrap();
+        assert_eq!(config.update_check.prerelease, Some(true));
+        assert_eq!(
+            config.update_check.source,
+            SourceConfig::CratesIo {
+                url: "https://registry.example.com".to_string()
+            }
+        );
+
+        fs::write(&path, "[update-checks]\n").unwrap();
+        assert!(matches!(
+            OrnConfig::from_file(&path),
+            Err(CoreError::InvalidConfig { .. })
+        ));
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,59 +1,891 @@
 use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
 
-use math_parse::MathParse;
+use clap::ValueEnum;
+use num_bigint::BigUint;
+use num_traits::One;
 use serde::Deserialize;
 
+use crate::comments::{default_template, render_comment, CommentValues};
+use crate::core_error::CoreError;
+use crate::dependency_graph::DependencyGraph;
+use crate::error_codes::{check_unique_codes, ErrorCode, ERRORS_KEY};
+use crate::expression::{Expression, ExpressionError};
+use crate::move_type::{vector_elements, MoveType};
+use crate::number_format::NumberFormat;
+use crate::package::MOVE_MANIFEST;
+
 const CONSTANT_VALUES: &str = include_str!("const_values.toml");
-const MAX_DEPTH: usize = 5;
 
-#[derive(Debug)]
-pub enum ConstantTypes {
-    String { value: String },
-    Number { value: String },
-    Hex { value: String },
+/// File names looked up next to `Move.toml` when no table is given explicitly.
+pub const CONSTANT_FILE_NAMES: [&str; 2] = ["orn.toml", "constants.toml"];
+
+/// Constant tables shipped with orn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Preset {
+    /// Constants of the StarkNet verifier contracts
+    StarknetVerifier,
+}
+
+impl Preset {
+    pub fn source(&self) -> &'static str {
+        match self {
+            Preset::StarknetVerifier => CONSTANT_VALUES,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
 pub struct ConstantValue {
-    pub r#type: String,
+    pub r#type: MoveType,
     pub value: String,
+    /// Template of the comment, see [`crate::comments`]. Derived integer constants default to
+    /// their expression.
     pub comment: Option<String>,
+    /// How the value of an integer constant is written once evaluated
+    #[serde(flatten)]
+    pub number_format: NumberFormat,
+    /// Value as written in the table, before evaluation
+    #[serde(skip)]
+    pub expression: String,
+    /// Message of an error code, see [`ErrorCode`]
+    #[serde(skip)]
+    pub error_message: Option<String>,
+    /// Constant holding the prime modulus of the field the value is evaluated in
+    pub field: Option<String>,
+    /// Whether to also define `NAME_MONTGOMERY`, the field element in Montgomery form
+    #[serde(default)]
+    pub montgomery: bool,
+    /// Whether the entry is generated from another one rather than written in the table
+    #[serde(skip)]
+    pub derived: bool,
+    /// Value of an integer constant or elements of a vector of integers, once resolved
+    #[serde(skip)]
+    pub resolved: Vec<BigUint>,
+}
+
+/// Suffix of the constant holding the Montgomery form of a field element.
+pub const MONTGOMERY_SUFFIX: &str = "_MONTGOMERY";
+
+impl ConstantValue {
+    /// The constant `name` multiplied by the Montgomery factor `R = 2^256`, in the same field.
+    fn montgomery_form(&self, name: &str) -> ConstantValue {
+        ConstantValue {
+            r#type: self.r#type,
+            value: format!("{} * 2 ** 256", name),
+            comment: Some(format!("{} in Montgomery form", name)),
+            number_format: self.number_format,
+            expression: String::new(),
+            error_message: None,
+            field: self.field.clone(),
+            montgomery: false,
+            derived: true,
+            resolved: vec![],
+        }
+    }
+}
+
+/// Key of the table entry holding the options applying to every constant of a table file, e.g.
+/// `options = { format = "decimal", underscores = true }`.
+const OPTIONS_KEY: &str = "options";
+
+/// Name of the constant stored under `key`, without its scope.
+pub fn constant_name(key: &str) -> &str {
+    key.rsplit_once("::").map_or(key, |(_, name)| name)
 }
 
-pub fn get_variables_map(a: &HashMap<String, ConstantValue>) -> HashMap<String, String> {
-    a.iter()
-        .map(|(k, v)| (k.clone(), v.value.to_string()))
-        .collect::<HashMap<_, _>>()
+/// Scope of the constant stored under `key`, empty for global constants.
+pub fn constant_scope(key: &str) -> &str {
+    key.rsplit_once("::").map_or("", |(scope, _)| scope)
 }
 
-pub fn get_constant_values() -> HashMap<String, ConstantValue> {
-    let mut constant_values = toml::from_str(CONSTANT_VALUES).unwrap();
-    for _ in 0..MAX_DEPTH {
-        let mut has_changed = false;
-        let variables_map = get_variables_map(&constant_values);
-        constant_values = constant_values
+/// `scope`, then its parents up to the global scope, e.g. `0x1::m`, `0x1` and ``.
+pub(crate) fn scope_chain(scope: &str) -> impl Iterator<Item = &str> {
+    let mut next = Some(scope);
+    std::iter::from_fn(move || {
+        let scope = next?;
+        next = match scope {
+            "" => None,
+            _ => Some(scope.rsplit_once("::").map_or("", |(parent, _)| parent)),
+        };
+        Some(scope)
+    })
+}
+
+/// Finds the entry the constant `name` refers to in `scope` (a module `addr::module`, an
+/// address, or empty for the global scope), the most specific scope taking precedence.
+pub fn lookup_constant<'a>(
+    table: &'a HashMap<String, ConstantValue>,
+    scope: &str,
+    name: &str,
+) -> Option<(&'a String, &'a ConstantValue)> {
+    scope_chain(scope).find_map(|scope| match scope {
+        "" => table.get_key_value(name),
+        _ => table.get_key_value(&format!("{}::{}", scope, name)),
+    })
+}
+
+/// Entry the identifier `reference` in the value of the constant `key` refers to. A scoped
+/// constant can refer to the constant of an outer scope it shadows.
+pub fn resolve_reference<'a>(
+    table: &'a HashMap<String, ConstantValue>,
+    key: &str,
+    reference: &str,
+) -> Option<&'a String> {
+    let mut scope = constant_scope(key);
+    if reference == constant_name(key) {
+        scope = constant_scope(scope);
+    }
+    lookup_constant(table, scope, reference).map(|(key, _)| key)
+}
+
+/// Keys of the entries the value and the field of the resolved constant `key` refer to, in
+/// order of appearance.
+pub fn constant_dependencies<'a>(
+    table: &'a HashMap<String, ConstantValue>,
+    key: &str,
+) -> Vec<&'a String> {
+    let Some(constant) = table.get(key) else {
+        return vec![];
+    };
+    let values = match constant.r#type.bits() {
+        Some(_) => vec![constant.expression.as_str()],
+        None if constant.r#type.element_bits().is_some() => {
+            vector_elements(&constant.expression).unwrap_or_default()
+        }
+        None => vec![],
+    };
+    let expressions: Vec<_> = values
+        .into_iter()
+        .filter_map(|value| Expression::parse(value).ok())
+        .collect();
+    let mut dependencies = vec![];
+    for reference in expressions
+        .iter()
+        .flat_map(|expression| expression.identifiers())
+        .chain(constant.field.as_deref())
+    {
+        if let Some(dependency) = resolve_reference(table, key, reference) {
+            if !dependencies.contains(&dependency) {
+                dependencies.push(dependency);
+            }
+        }
+    }
+    dependencies
+}
+
+/// Returns the resolved constants of the built-in StarkNet verifier table.
+pub fn get_constant_values() -> Result<HashMap<String, ConstantValue>, CoreError> {
+    load_constant_values(Some(Preset::StarknetVerifier), &[])
+}
+
+/// Resolves the constant table `source` as [`load_constant_values`] resolves a file.
+#[cfg(test)]
+pub(crate) fn table_from_str(source: &str) -> Result<HashMap<String, ConstantValue>, CoreError> {
+    let constant_values = resolve_constant_values(parse_constant_table(source, "test")?)?;
+    check_unique_codes(&constant_values)?;
+    Ok(constant_values)
+}
+
+/// Looks for the closest directory containing a `Move.toml`, starting from `dir`,
+/// and returns the constant tables (see [`CONSTANT_FILE_NAMES`]) that sit next to it.
+pub fn discover_constant_files(dir: &Path) -> Vec<PathBuf> {
+    let Some(package_dir) = dir
+        .ancestors()
+        .find(|ancestor| ancestor.join(MOVE_MANIFEST).is_file())
+    else {
+        return vec![];
+    };
+    CONSTANT_FILE_NAMES
+        .iter()
+        .map(|name| package_dir.join(name))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// Loads the preset (if any) followed by every file in `files`, then resolves the merged table.
+///
+/// Entries of a later source override entries with the same name from earlier ones.
+pub fn load_constant_values(
+    preset: Option<Preset>,
+    files: &[PathBuf],
+) -> Result<HashMap<String, ConstantValue>, CoreError> {
+    let mut constant_values = HashMap::new();
+    if let Some(preset) = preset {
+        constant_values.extend(parse_constant_table(
+            preset.source(),
+            &format!("{:?}", preset),
+        )?);
+    }
+    for file in files {
+        let content = fs::read_to_string(file)?;
+        constant_values.extend(parse_constant_table(&content, &file.display().to_string())?);
+    }
+    let constant_values = resolve_constant_values(constant_values)?;
+    check_unique_codes(&constant_values)?;
+    Ok(constant_values)
+}
+
+/// Parses a constant table file.
+///
+/// Besides global constants, a table may have sections scoped to a module or an address, whose
+/// constants are stored under `scope::NAME`:
+///
+/// ```toml
+/// MAX_N_QUERIES = { type = "u64", value = "48" }
+///
+/// ["verifier_addr::fri_layer"]
+/// MAX_N_QUERIES = { type = "u64", value = "32" }
+/// ```
+///
+/// Error codes (see [`ERRORS_KEY`]) are stored as `u64` constants and field elements with a
+/// Montgomery form get a second entry, see [`MONTGOMERY_SUFFIX`].
+fn parse_constant_table(
+    content: &str,
+    source: &str,
+) -> Result<HashMap<String, ConstantValue>, CoreError> {
+    let table_error = |err| CoreError::ConstantTableError {
+        source_name: source.to_string(),
+        err,
+    };
+    let entry_error = |name: &str, err| CoreError::ConstantEntryError {
+        source_name: source.to_string(),
+        name: name.to_string(),
+        err: Box::new(err),
+    };
+    let scoped = |scope: &str, name: &str| match scope {
+        "" => name.to_string(),
+        scope => format!("{}::{}", scope, name),
+    };
+    let insert_errors = |constants: &mut HashMap<String, ConstantValue>,
+                         scope: &str,
+                         errors: toml::Value|
+     -> Result<(), CoreError> {
+        let errors: toml::Table = errors
+            .try_into()
+            .map_err(|err| entry_error(&scoped(scope, ERRORS_KEY), err))?;
+        for (name, value) in errors {
+            let key = scoped(scope, &name);
+            let error: ErrorCode = value.try_into().map_err(|err| entry_error(&key, err))?;
+            constants.insert(key, error.into());
+        }
+        Ok(())
+    };
+
+    let table: toml::Table = toml::from_str(content).map_err(table_error)?;
+    let mut options = NumberFormat::default();
+    let mut constants = HashMap::<String, ConstantValue>::new();
+    for (key, value) in table {
+        if key == OPTIONS_KEY {
+            options = value.try_into().map_err(table_error)?;
+            continue;
+        }
+        if key == ERRORS_KEY {
+            insert_errors(&mut constants, "", value)?;
+            continue;
+        }
+        match value {
+            // a section is a table of tables, a constant has a `type` and a `value`
+            toml::Value::Table(section)
+                if section.values().all(|value| value.is_table()) && !section.is_empty() =>
+            {
+                for (name, value) in section {
+                    if name == ERRORS_KEY {
+                        insert_errors(&mut constants, &key, value)?;
+                        continue;
+                    }
+                    let key = scoped(&key, &name);
+                    let constant = value.try_into().map_err(|err| entry_error(&key, err))?;
+                    constants.insert(key, constant);
+                }
+            }
+            value => {
+                let constant = value.try_into().map_err(|err| entry_error(&key, err))?;
+                constants.insert(key, constant);
+            }
+        }
+    }
+    for constant in constants.values_mut() {
+        constant.number_format = constant.number_format.or(options);
+    }
+    let montgomery_forms: Vec<_> = constants
+        .iter()
+        .filter(|(_, constant)| constant.montgomery)
+        .map(|(key, constant)| {
+            let form = constant.montgomery_form(constant_name(key));
+            (format!("{}{}", key, MONTGOMERY_SUFFIX), form)
+        })
+        .collect();
+    constants.extend(montgomery_forms);
+    Ok(constants)
+}
+
+/// Integer expressions of a constant and the width they are evaluated at: the value of an
+/// integer constant, the elements of a vector of integers, `None` for the other values.
+fn integer_values(constant: &ConstantValue) -> Option<(u32, Vec<&str>)> {
+    if let Some(bits) = constant.r#type.bits() {
+        return Some((bits, vec![constant.value.as_str()]));
+    }
+    let bits = constant.r#type.element_bits()?;
+    Some((bits, vector_elements(&constant.value)?))
+}
+
+/// Evaluates the integer constants and the elements of vectors of integers and replaces their
+/// value with the result, rendered with their number format. The original value is kept in
+/// `expression`. Constants with a `field` are evaluated modulo the value of that constant.
+///
+/// Values of the other types are checked to be literals of their type and kept as they are.
+fn resolve_constant_values(
+    mut constant_values: HashMap<String, ConstantValue>,
+) -> Result<HashMap<String, ConstantValue>, CoreError> {
+    for constant in constant_values.values_mut() {
+        constant.expression = constant.value.clone();
+        if integer_values(constant).is_none() {
+            constant.value = constant.value.trim().to_string();
+        }
+    }
+    for (name, constant) in &constant_values {
+        let invalid = |reason: &str| CoreError::InvalidConstant {
+            name: name.clone(),
+            value: constant.value.clone(),
+            r#type: constant.r#type,
+            reason: reason.to_string(),
+        };
+        if constant.field.is_some() && integer_values(constant).is_none() {
+            return Err(invalid(
+                "only integers and vectors of integers can be field elements",
+            ));
+        }
+        if constant.montgomery && constant.field.is_none() {
+            return Err(invalid("a Montgomery form needs a `field`"));
+        }
+        if integer_values(constant).is_none() {
+            constant
+                .r#type
+                .validate_literal(&constant.value)
+                .map_err(|reason| CoreError::InvalidConstant {
+                    name: name.clone(),
+                    value: constant.value.clone(),
+                    r#type: constant.r#type,
+                    reason,
+                })?;
+        }
+    }
+    let mut expressions = HashMap::new();
+    let mut graph = DependencyGraph::default();
+    for (name, constant) in &constant_values {
+        let Some((_, values)) = integer_values(constant) else {
+            continue;
+        };
+        let values = values
             .into_iter()
-            .map(|(k, mut v)| {
-                let value = match MathParse::parse(&v.value) {
-                    Err(_) => v.value,
-                    Ok(expression) => match expression.solve_int(Some(&variables_map)) {
-                        Err(_) => v.value,
-                        Ok(result) => {
-                            let result = format!("0x{:x}", result).to_string();
-                            if v.value != result {
-                                has_changed = true
-                            }
-                            result
+            .map(|value| {
+                Expression::parse(value).map_err(|err| CoreError::ExpressionError {
+                    name: name.clone(),
+                    expression: constant.value.clone(),
+                    err,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        graph.add_node(name);
+        let mut references = HashMap::new();
+        let field = constant.field.as_deref();
+        for reference in values
+            .iter()
+            .flat_map(|value| value.identifiers())
+            .chain(field)
+        {
+            let Some(key) = resolve_reference(&constant_values, name, reference) else {
+                return Err(CoreError::UndefinedConstant {
+                    name: name.clone(),
+                    expression: constant.value.clone(),
+                    reference: reference.to_string(),
+                });
+            };
+            graph.add_dependency(name, key);
+            references.insert(reference.to_string(), key.clone());
+        }
+        let field = field.map(|field| references[field].clone());
+        expressions.insert(name.clone(), (values, references, field));
+    }
+    let order = graph
+        .topological_order()
+        .map_err(|cycle| CoreError::DependencyCycle { cycle })?;
+
+    // integer constants, which other constants can refer to, and vectors of integers
+    let mut resolved = HashMap::<String, BigUint>::new();
+    let mut vectors = HashMap::<String, Vec<BigUint>>::new();
+    // expressions with the constants they reference expanded, for the comments
+    let mut expanded = HashMap::<String, Vec<Expression>>::new();
+    for name in order {
+        let Some((values, references, field)) = expressions.get(&name) else {
+            continue;
+        };
+        let constant = &constant_values[&name];
+        let Some((bits, _)) = integer_values(constant) else {
+            continue;
+        };
+        let lookup = |name: &str| resolved.get(references.get(name)?).cloned();
+        let invalid = |reason: String| CoreError::InvalidConstant {
+            name: name.clone(),
+            value: constant.value.clone(),
+            r#type: constant.r#type,
+            reason,
+        };
+        let modulus = match field {
+            None => None,
+            Some(field) => match resolved.get(field) {
+                Some(modulus) if *modulus > BigUint::one() => Some(modulus),
+                Some(_) => {
+                    return Err(invalid(format!(
+                        "the modulus {} must be greater than 1",
+                        field
+                    )))
+                }
+                None => return Err(invalid(format!("the field {} is not an integer", field))),
+            },
+        };
+        let mut results = vec![];
+        for expression in values {
+            let result = match modulus {
+                Some(modulus) => expression
+                    .evaluate_in_field(modulus, &lookup)
+                    .and_then(|value| {
+                        if value.bits() > u64::from(bits) {
+                            return Err(ExpressionError::Overflow("field element", bits));
                         }
-                    },
+                        Ok(value)
+                    }),
+                None => expression.evaluate(bits, &lookup),
+            };
+            let value = result.map_err(|err| {
+                // report the width the value actually needs when it fits in the widest type
+                let wide = match err {
+                    ExpressionError::Overflow(..) if modulus.is_none() => {
+                        expression.evaluate(256, &lookup).ok()
+                    }
+                    _ => None,
                 };
-                v.value = value;
-                (k, v)
-            })
-            .collect::<HashMap<_, _>>();
-        if !has_changed {
-            break;
+                match wide {
+                    Some(value) => invalid(format!(
+                        "0x{:x} is a {}-bit value, it does not fit in {} bits",
+                        value,
+                        value.bits(),
+                        bits
+                    )),
+                    None => CoreError::ExpressionError {
+                        name: name.clone(),
+                        expression: constant.value.clone(),
+                        err,
+                    },
+                }
+            })?;
+            results.push(value);
+        }
+        let expand = |reference: &str| match expanded.get(references.get(reference)?)?.as_slice() {
+            [expression] => Some(expression.clone()),
+            _ => None,
+        };
+        let expansion = values
+            .iter()
+            .map(|value| value.substitute(&expand))
+            .collect();
+        expanded.insert(name.clone(), expansion);
+        if constant.r#type.bits().is_some() {
+            resolved.insert(name, results.remove(0));
+        } else {
+            vectors.insert(name, results);
+        }
+    }
+    for (name, value) in resolved {
+        if let Some(constant) = constant_values.get_mut(&name) {
+            let bits = constant.r#type.bits().unwrap_or(256);
+            constant.value = constant.number_format.render(&value, bits);
+            constant.resolved = vec![value];
+        }
+    }
+    for (name, elements) in vectors {
+        if let Some(constant) = constant_values.get_mut(&name) {
+            let bits = constant.r#type.element_bits().unwrap_or(256);
+            let rendered: Vec<_> = elements
+                .iter()
+                .map(|element| constant.number_format.render(element, bits))
+                .collect();
+            constant.value = format!("vector[{}]", rendered.join(", "));
+            constant.resolved = elements;
+        }
+    }
+    for (name, constant) in constant_values.iter_mut() {
+        let expansion = expanded.get(name.as_str());
+        let template = match (&constant.comment, expansion) {
+            (Some(comment), _) => comment.clone(),
+            (None, Some(expansion)) => match default_template(expansion) {
+                Some(template) => template.to_string(),
+                None => continue,
+            },
+            (None, None) => continue,
+        };
+        let is_vector =
+            constant.r#type.element_bits().is_some() && constant.r#type.bits().is_none();
+        let expanded = match expansion {
+            Some(expansion) => {
+                let expressions: Vec<_> = expansion.iter().map(|e| e.to_string()).collect();
+                match is_vector {
+                    true => format!("[{}]", expressions.join(", ")),
+                    false => expressions.join(", "),
+                }
+            }
+            None => constant.expression.trim().to_string(),
+        };
+        let values = CommentValues {
+            expression: constant.expression.trim(),
+            expanded,
+            values: &constant.resolved,
+            is_vector: is_vector && expansion.is_some(),
+        };
+        let comment =
+            render_comment(&template, &values).map_err(|reason| CoreError::InvalidConstant {
+                name: name.clone(),
+                value: constant.expression.clone(),
+                r#type: constant.r#type,
+                reason,
+            })?;
+        constant.comment = Some(comment);
+    }
+    Ok(constant_values)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use crate::const_values::{
+        load_constant_values, lookup_constant, parse_constant_table, resolve_constant_values,
+        Preset,
+    };
+    use crate::core_error::CoreError;
+    use crate::move_type::MoveType;
+
+    #[test]
+    fn test_user_table_overrides_preset() {
+        let path = std::env::temp_dir().join("orn_test_user_table_overrides_preset.toml");
+        fs::write(
+            &path,
+            r#"
+FRI_MAX_STEP_SIZE = { type = "u256", value = "3" }
+MY_CONSTANT = { type = "u64", value = "MAX_COSET_SIZE + 1" }
+"#,
+        )
+        .unwrap();
+        let files = vec![path];
+        let table = load_constant_values(Some(Preset::StarknetVerifier), &files).unwrap();
+        fs::remove_file(&files[0]).unwrap();
+        assert_eq!(table["FRI_MAX_STEP_SIZE"].value, "0x3");
+        assert_eq!(table["MAX_COSET_SIZE"].value, "0x8");
+        assert_eq!(table["MY_CONSTANT"].value, "0x9");
+    }
+
+    #[test]
+    fn test_derived_constants() {
+        let table = load_constant_values(Some(Preset::StarknetVerifier), &[]).unwrap();
+        assert_eq!(
+            table["FRI_GROUP_GEN"].value,
+            "0x5ec467b88826aba4537602d514425f3b0bdf467bbf302458337c45f6021e539"
+        );
+        assert_eq!(
+            table["K_MONTGOMERY_R"].value,
+            "0x7fffffffffffdf0ffffffffffffffffffffffffffffffffffffffffffffffe1"
+        );
+        assert_eq!(
+            table["K_MONTGOMERY_R_INV"].value,
+            "0x40000000000001100000000000012100000000000000000000000000000000"
+        );
+    }
+
+    #[test]
+    fn test_comment_templates() {
+        let table = parse_constant_table(
+            r#"
+MAX_COSET_SIZE = { type = "u64", value = "1 << 4" }
+GROUP_SIZE = { type = "u64", value = "32 * MAX_COSET_SIZE", comment = "{expression} = {expanded} = {decimal} ({hex})" }
+OFFSETS = { type = "vector<u8>", value = "[MAX_COSET_SIZE, 2]", comment = "{expanded} = {decimal}" }
+N_QUERIES = { type = "u64", value = "48" }
+NAME = { type = "vector<u8>", value = "b\"orn\"", comment = "{expression}" }
+"#,
+            "test",
+        )
+        .unwrap();
+        let table = resolve_constant_values(table).unwrap();
+        let comment = |name: &str| table[name].comment.as_deref();
+        assert_eq!(comment("MAX_COSET_SIZE"), Some("1 << 4"));
+        assert_eq!(
+            comment("GROUP_SIZE"),
+            Some("32 * MAX_COSET_SIZE = 32 * (1 << 4) = 512 (0x200)")
+        );
+        assert_eq!(comment("OFFSETS"), Some("[1 << 4, 2] = [16, 2]"));
+        assert_eq!(comment("N_QUERIES"), None);
+        assert_eq!(comment("NAME"), Some("b\"orn\""));
+
+        let table = parse_constant_table(
+            r#"NAME = { type = "vector<u8>", value = "b\"orn\"", comment = "{decimal}" }"#,
+            "test",
+        )
+        .unwrap();
+        assert!(matches!(
+            resolve_constant_values(table),
+            Err(CoreError::InvalidConstant { .. })
+        ));
+    }
+
+    #[test]
+    fn test_field_elements() {
+        let table = parse_constant_table(
+            r#"
+K_MODULUS = { type = "u256", value = "0x800000000000011000000000000000000000000000000000000000000000001" }
+SMALL_PRIME = { type = "u64", value = "17" }
+MINUS_ONE = { type = "u256", value = "0 - 1", field = "K_MODULUS" }
+HALF = { type = "u64", value = "1 / 2", field = "SMALL_PRIME", montgomery = true, format = "decimal" }
+POWERS = { type = "vector<u64>", value = "[3 ** 16, 3 ** 8]", field = "SMALL_PRIME" }
+"#,
+            "test",
+        )
+        .unwrap();
+        assert_eq!(table["HALF_MONTGOMERY"].expression, "");
+        let table = resolve_constant_values(table).unwrap();
+        assert_eq!(
+            table["MINUS_ONE"].value,
+            "0x800000000000011000000000000000000000000000000000000000000000000"
+        );
+        assert_eq!(table["HALF"].value, "9");
+        // 9 * 2^256 mod 17, 2^256 = (2^8)^32 = 1 mod 17
+        assert_eq!(table["HALF_MONTGOMERY"].value, "9");
+        assert_eq!(
+            table["HALF_MONTGOMERY"].comment.as_deref(),
+            Some("HALF in Montgomery form")
+        );
+        assert_eq!(table["POWERS"].value, "vector[0x1, 0x10]");
+
+        for entry in [
+            r#"A = { type = "u64", value = "1", montgomery = true }"#,
+            r#"A = { type = "bool", value = "true", field = "P" }
+P = { type = "u64", value = "17" }"#,
+            r#"A = { type = "u64", value = "1", field = "P" }
+P = { type = "u64", value = "1" }"#,
+        ] {
+            let table = parse_constant_table(entry, "test").unwrap();
+            assert!(matches!(
+                resolve_constant_values(table),
+                Err(CoreError::InvalidConstant { .. })
+            ));
+        }
+        let table =
+            parse_constant_table(r#"A = { type = "u64", value = "1", field = "P" }"#, "test")
+                .unwrap();
+        assert_eq!(
+            resolve_constant_values(table).unwrap_err().to_string(),
+            "A = 1 references undefined constant P"
+        );
+    }
+
+    #[test]
+    fn test_number_format() {
+        let table = parse_constant_table(
+            r#"
+options = { format = "decimal", underscores = true }
+SLOT_SIZE = { type = "u64", value = "3" }
+MODULUS = { type = "u256", value = "0x800000000000011000000000000000000000000000000000000000000000001" }
+MASK = { type = "u32", value = "0xff", format = "upper-hex", padded = true }
+"#,
+            "test",
+        )
+        .unwrap();
+        let table = resolve_constant_values(table).unwrap();
+        assert_eq!(table["SLOT_SIZE"].value, "3");
+        assert_eq!(
+            table["MODULUS"].value,
+            "3_618_502_788_666_131_213_697_322_783_095_070_105_623_107_215_331_596_699_973_092_056_135_872_020_481"
+        );
+        assert_eq!(table["MASK"].value, "0x0000_00FF");
+        assert_eq!(table["MASK"].expression, "0xff");
+    }
+
+    #[test]
+    fn test_scoped_constants() {
+        let table = parse_constant_table(
+            r#"
+MAX_N_QUERIES = { type = "u64", value = "48" }
+EINVALID_INPUT = { type = "u64", value = "1" }
+
+["verifier_addr"]
+EINVALID_INPUT = { type = "u64", value = "2" }
+
+["verifier_addr::fri_layer"]
+MAX_N_QUERIES = { type = "u64", value = "MAX_N_QUERIES / 2" }
+EINVALID_INPUT = { type = "u64", value = "EINVALID_INPUT + 1" }
+"#,
+            "test",
+        )
+        .unwrap();
+        let table = resolve_constant_values(table).unwrap();
+        let value =
+            |scope: &str, name: &str| &lookup_constant(&table, scope, name).unwrap().1.value;
+        assert_eq!(value("verifier_addr::fri_layer", "MAX_N_QUERIES"), "0x18");
+        assert_eq!(value("verifier_addr::fri_layer", "EINVALID_INPUT"), "0x3");
+        assert_eq!(
+            value("verifier_addr::fact_registry", "MAX_N_QUERIES"),
+            "0x30"
+        );
+        assert_eq!(
+            value("verifier_addr::fact_registry", "EINVALID_INPUT"),
+            "0x2"
+        );
+        assert_eq!(value("0x1::vector", "EINVALID_INPUT"), "0x1");
+        assert_eq!(value("", "EINVALID_INPUT"), "0x1");
+        assert!(lookup_constant(&table, "0x1::vector", "UNKNOWN").is_none());
+    }
+
+    #[test]
+    fn test_error_codes() {
+        let table = parse_constant_table(
+            r#"
+options = { format = "hex" }
+
+[errors]
+EINVALID_INPUT = { code = 10, message = "The input is invalid" }
+
+["verifier_addr::fri_layer".errors]
+EINVALID_STEP = { code = 11, message = "The FRI step is not supported" }
+"#,
+            "test",
+        )
+        .unwrap();
+        let table = resolve_constant_values(table).unwrap();
+        assert_eq!(table["EINVALID_INPUT"].value, "10");
+        assert_eq!(table["EINVALID_INPUT"].r#type, MoveType::U64);
+        assert_eq!(
+            table["verifier_addr::fri_layer::EINVALID_STEP"]
+                .error_message
+                .as_deref(),
+            Some("The FRI step is not supported")
+        );
+
+        let err = parse_constant_table(
+            "[errors]\nEINVALID_INPUT = { code = 1, msg = \"typo\" }",
+            "test",
+        )
+        .unwrap_err();
+        assert!(
+            matches!(err, CoreError::ConstantEntryError { name, .. } if name == "EINVALID_INPUT")
+        );
+    }
+
+    #[test]
+    fn test_value_does_not_fit_type() {
+        let table = toml::from_str(
+            r#"
+MASK = { type = "u256", value = "0xff" }
+SMALL_MASK = { type = "u8", value = "MASK + 1" }
+"#,
+        )
+        .unwrap();
+        let err = resolve_constant_values(table).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "constant SMALL_MASK: u8 = MASK + 1 is invalid: 0x100 is a 9-bit value, it does not fit in 8 bits"
+        );
+    }
+
+    #[test]
+    fn test_deep_chain() {
+        let table = toml::from_str(
+            &(0..20)
+                .map(|i| {
+                    format!(
+                        "C{} = {{ type = \"u64\", value = \"C{} + 1\" }}\n",
+                        i + 1,
+                        i
+                    )
+                })
+                .chain(["C0 = { type = \"u64\", value = \"0\" }".to_string()])
+                .collect::<String>(),
+        )
+        .unwrap();
+        assert_eq!(resolve_constant_values(table).unwrap()["C20"].value, "0x14");
+    }
+
+    #[test]
+    fn test_undefined_and_cyclic_constants() {
+        let table = toml::from_str(r#"A = { type = "u64", value = "B + 1" }"#).unwrap();
+        assert_eq!(
+            resolve_constant_values(table).unwrap_err().to_string(),
+            "A = B + 1 references undefined constant B"
+        );
+
+        let table = toml::from_str(
+            r#"
+A = { type = "u64", value = "B + 1" }
+B = { type = "u64", value = "C * 2" }
+C = { type = "u64", value = "A" }
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            resolve_constant_values(table).unwrap_err().to_string(),
+            "cyclic constant definitions: A -> B -> C -> A"
+        );
+    }
+
+    #[test]
+    fn test_vectors_and_literals() {
+        let table = toml::from_str(
+            r#"
+N_QUERIES = { type = "u64", value = "48" }
+QUERY_SIZES = { type = "vector<u64>", value = "vector[1, N_QUERIES * 2, (1 << 4),]" }
+SLOTS = { type = "vector<u8>", value = "[1, 2]", format = "decimal" }
+EMPTY = { type = "vector<u128>", value = "[]" }
+TAG = { type = "vector<u8>", value = 'b"STARKNET\n"' }
+OWNER = { type = "address", value = " @verifier_addr " }
+ENABLED = { type = "bool", value = "true" }
+"#,
+        )
+        .unwrap();
+        let table = resolve_constant_values(table).unwrap();
+        assert_eq!(table["QUERY_SIZES"].value, "vector[0x1, 0x60, 0x10]");
+        assert_eq!(table["QUERY_SIZES"].r#type, MoveType::IntegerVector(64));
+        assert_eq!(table["SLOTS"].value, "vector[1, 2]");
+        assert_eq!(table["EMPTY"].value, "vector[]");
+        assert_eq!(table["TAG"].value, "b\"STARKNET\\n\"");
+        assert_eq!(table["OWNER"].value, "@verifier_addr");
+        assert_eq!(table["ENABLED"].value, "true");
+
+        let table =
+            toml::from_str(r#"SIZES = { type = "vector<u8>", value = "[1, 256]" }"#).unwrap();
+        assert_eq!(
+            resolve_constant_values(table).unwrap_err().to_string(),
+            "constant SIZES: vector<u8> = [1, 256] is invalid: 0x100 is a 9-bit value, it does not fit in 8 bits"
+        );
+    }
+
+    #[test]
+    fn test_invalid_literals() {
+        for (r#type, value) in [
+            ("bool", "1"),
+            ("address", "0x1"),
+            ("vector<u8>", "x\"abc\""),
+            ("vector<u8>", "STARKNET"),
+            ("vector<u8>", "b\"\\q\""),
+            ("vector<u64>", "1, 2"),
+        ] {
+            let table = toml::from_str(&format!(
+                "CONSTANT = {{ type = \"{}\", value = '{}' }}",
+                r#type, value
+            ))
+            .unwrap();
+            assert!(matches!(
+                resolve_constant_values(table),
+                Err(CoreError::InvalidConstant { .. })
+            ));
         }
     }
-    constant_values
 }
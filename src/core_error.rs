@@ -2,6 +2,9 @@ use std::io::Error;
 
 use glob::{GlobError, PatternError};
 
+use crate::expression::ExpressionError;
+use crate::move_type::MoveType;
+
 #[derive(thiserror::Error, Debug)]
 pub enum CoreError {
     #[error("pattern error {0}")]
@@ -12,4 +15,72 @@ pub enum CoreError {
 
     #[error("io error {0}")]
     IOError(#[from] Error),
+
+    #[error("invalid constant table {source_name}: {err}")]
+    ConstantTableError {
+        source_name: String,
+        err: toml::de::Error,
+    },
+
+    #[error("invalid constant {name} in {source_name}: {err}")]
+    ConstantEntryError {
+        source_name: String,
+        name: String,
+        err: Box<toml::de::Error>,
+    },
+
+    #[error("cannot evaluate {name} = {expression}: {err}")]
+    ExpressionError {
+        name: String,
+        expression: String,
+        err: ExpressionError,
+    },
+
+    #[error("constant {name}: {type} = {value} is invalid: {reason}", type = r#type)]
+    InvalidConstant {
+        name: String,
+        value: String,
+        r#type: MoveType,
+        reason: String,
+    },
+
+    #[error("{name} = {expression} references undefined constant {reference}")]
+    UndefinedConstant {
+        name: String,
+        expression: String,
+        reference: String,
+    },
+
+    #[error("cyclic constant definitions: {}", cycle.join(" -> "))]
+    DependencyCycle { cycle: Vec<String> },
+
+    #[error("error codes {} have the same code {code}", names.join(", "))]
+    DuplicateErrorCode { code: String, names: Vec<String> },
+
+    #[error("invalid undo journal {path}: {err}")]
+    InvalidJournal { path: String, err: String },
+
+    #[error("invalid Move manifest {path}: {err}")]
+    InvalidManifest {
+        path: String,
+        err: Box<toml::de::Error>,
+    },
+
+    #[error("invalid orn config {path}: {err}")]
+    InvalidConfig {
+        path: String,
+        err: Box<toml::de::Error>,
+    },
+
+    #[error("no Move package found in {0}")]
+    NoPackage(String),
+
+    #[error("unknown constant {0}")]
+    UnknownConstant(String),
+
+    #[error("malformed Move file: {0}")]
+    MalformedMoveFile(String),
+
+    #[error("{path}: {err}")]
+    FileError { path: String, err: Box<CoreError> },
 }
@@ -0,0 +1,106 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Directed graph from each constant to the constants its expression references.
+#[derive(Debug, Default, Clone)]
+pub struct DependencyGraph {
+    dependencies: BTreeMap<String, BTreeSet<String>>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Visit {
+    InProgress,
+    Done,
+}
+
+impl DependencyGraph {
+    pub fn add_node(&mut self, name: &str) {
+        self.dependencies.entry(name.to_string()).or_default();
+    }
+
+    pub fn add_dependency(&mut self, name: &str, dependency: &str) {
+        self.add_node(dependency);
+        self.dependencies
+            .entry(name.to_string())
+            .or_default()
+            .insert(dependency.to_string());
+    }
+
+    pub fn dependencies(&self, name: &str) -> impl Iterator<Item = &String> {
+        self.dependencies.get(name).into_iter().flatten()
+    }
+
+    /// Orders the nodes so that every node comes after its dependencies.
+    ///
+    /// Fails with the path of the first cycle found, starting and ending with the same node,
+    /// e.g. `[A, B, A]`.
+    pub fn topological_order(&self) -> Result<Vec<String>, Vec<String>> {
+        let mut visits = HashMap::<&str, Visit>::new();
+        let mut order = Vec::with_capacity(self.dependencies.len());
+        for name in self.dependencies.keys() {
+            let mut path = vec![];
+            self.visit(name, &mut visits, &mut path, &mut order)?;
+        }
+        Ok(order)
+    }
+
+    fn visit<'a>(
+        &'a self,
+        name: &'a str,
+        visits: &mut HashMap<&'a str, Visit>,
+        path: &mut Vec<&'a str>,
+        order: &mut Vec<String>,
+    ) -> Result<(), Vec<String>> {
+        match visits.get(name) {
+            Some(Visit::Done) => return Ok(()),
+            Some(Visit::InProgress) => {
+                let start = path.iter().position(|node| *node == name).unwrap_or(0);
+                let mut cycle: Vec<_> = path[start..].iter().map(|node| node.to_string()).collect();
+                cycle.push(name.to_string());
+                return Err(cycle);
+            }
+            None => {}
+        }
+        visits.insert(name, Visit::InProgress);
+        path.push(name);
+        for dependency in self.dependencies(name) {
+            self.visit(dependency, visits, path, order)?;
+        }
+        path.pop();
+        visits.insert(name, Visit::Done);
+        order.push(name.to_string());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dependency_graph::DependencyGraph;
+
+    #[test]
+    fn test_topological_order() {
+        let mut graph = DependencyGraph::default();
+        graph.add_dependency("FRI_CTX_SIZE", "FRI_GROUP_SIZE");
+        graph.add_dependency("FRI_GROUP_SIZE", "MAX_COSET_SIZE");
+        graph.add_dependency("MAX_COSET_SIZE", "FRI_MAX_STEP_SIZE");
+        graph.add_node("K_MODULUS");
+        assert_eq!(
+            graph.topological_order().unwrap(),
+            vec![
+                "FRI_MAX_STEP_SIZE",
+                "MAX_COSET_SIZE",
+                "FRI_GROUP_SIZE",
+                "FRI_CTX_SIZE",
+                "K_MODULUS"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cycle() {
+        let mut graph = DependencyGraph::default();
+        graph.add_dependency("A", "B");
+        graph.add_dependency("B", "C");
+        graph.add_dependency("C", "B");
+        assert_eq!(graph.topological_order().unwrap_err(), vec!["B", "C", "B"]);
+    }
+}
@@ -0,0 +1,401 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Write;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::comments::stale_comments;
+use crate::const_values::ConstantValue;
+use crate::expression::Expression;
+use crate::package::MovePackage;
+
+/// What `gen_consts` noticed in one file, see [`Diagnostics`].
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Findings {
+    /// Keys of the table entries the file uses
+    pub used: BTreeSet<String>,
+    /// Modules and `SCREAMING_CASE` identifiers they reference that are neither declared
+    /// in the module nor in the table
+    pub missing: Vec<(String, String)>,
+    /// Modules and constants they declare by hand that the table also defines
+    pub shadowed: Vec<(String, String)>,
+    /// Modules and the keys of the table entries they use
+    pub uses: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DiagnosticKind {
+    /// A table entry no file uses
+    Unused,
+    /// An identifier looking like a constant that the table does not define
+    Missing,
+    /// A constant declared in a module that the table also defines
+    Shadowed,
+    /// A comment that is a number or an expression other than the value of its constant
+    StaleComment,
+    /// An address constant using a named address its package does not declare
+    UndeclaredAddress,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    pub name: String,
+    /// Files and modules the diagnostic applies to, e.g. `sources/a.move (0x1::a)`
+    pub locations: Vec<String>,
+    /// What is wrong, for the diagnostics that need more than a name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DiagnosticsFormat {
+    Text,
+    Json,
+}
+
+/// Findings of every file processed in a run.
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    used: BTreeSet<String>,
+    missing: BTreeMap<String, BTreeSet<String>>,
+    shadowed: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl Diagnostics {
+    pub fn add(&mut self, file_name: &str, findings: Findings) {
+        let location = |module: &str| format!("{} ({})", file_name, module);
+        self.used.extend(findings.used);
+        for (module, name) in findings.missing {
+            self.missing
+                .entry(name)
+                .or_default()
+                .insert(location(&module));
+        }
+        for (module, name) in findings.shadowed {
+            self.shadowed
+                .entry(name)
+                .or_default()
+                .insert(location(&module));
+        }
+    }
+
+    /// Diagnostics of the run, table entries not used by any file and stale comments included.
+    /// Derived entries, such as Montgomery forms, are never reported as unused.
+    pub fn report(&self, table: &HashMap<String, ConstantValue>) -> Vec<Diagnostic> {
+        let mut unused: Vec<_> = table
+            .iter()
+            .filter(|(key, constant)| !constant.derived && !self.used.contains(*key))
+            .map(|(key, _)| key)
+            .collect();
+        unused.sort();
+        let unused = unused.into_iter().map(|key| Diagnostic {
+            kind: DiagnosticKind::Unused,
+            name: key.clone(),
+            locations: vec![],
+            detail: None,
+        });
+        let located = |kind, entries: &BTreeMap<String, BTreeSet<String>>| {
+            entries
+                .iter()
+                .map(|(name, locations)| Diagnostic {
+                    kind,
+                    name: name.clone(),
+                    locations: locations.iter().cloned().collect(),
+                    detail: None,
+                })
+                .collect::<Vec<_>>()
+        };
+        unused
+            .chain(located(DiagnosticKind::Missing, &self.missing))
+            .chain(located(DiagnosticKind::Shadowed, &self.shadowed))
+            .chain(stale_comments(table).into_iter().map(|stale| Diagnostic {
+                kind: DiagnosticKind::StaleComment,
+                name: stale.key,
+                locations: vec![],
+                detail: Some(match Expression::parse(&stale.comment) {
+                    Ok(Expression::Number(_)) => format!(
+                        "the comment says {}, the value is 0x{:x}",
+                        stale.comment, stale.value
+                    ),
+                    _ => format!(
+                        "the comment says {} = 0x{:x}, the value is 0x{:x}",
+                        stale.comment, stale.stated, stale.value
+                    ),
+                }),
+            }))
+            .collect()
+    }
+}
+
+impl Diagnostics {
+    /// `address` constants used by the files of `package` whose named address the package
+    /// does not declare, which is only fine when one of its dependencies does.
+    pub fn undeclared_addresses(
+        &self,
+        table: &HashMap<String, ConstantValue>,
+        package: &MovePackage,
+    ) -> Vec<Diagnostic> {
+        package
+            .undeclared_addresses(table, &self.used)
+            .into_iter()
+            .map(|(key, address)| Diagnostic {
+                kind: DiagnosticKind::UndeclaredAddress,
+                name: key,
+                locations: vec![],
+                detail: Some(format!(
+                    "@{} is not declared by package {}",
+                    address, package.name
+                )),
+            })
+            .collect()
+    }
+}
+
+/// Renders `diagnostics` as a single summary, empty in text format if there is nothing to report.
+/// In text format, unused constants are only counted unless `verbose` is set.
+pub fn render_diagnostics(
+    diagnostics: &[Diagnostic],
+    format: DiagnosticsFormat,
+    verbose: bool,
+) -> String {
+    match format {
+        DiagnosticsFormat::Json => {
+            serde_json::to_string_pretty(diagnostics).unwrap_or_default() + "\n"
+        }
+        DiagnosticsFormat::Text => {
+            let mut result = String::new();
+            let of_kind = |kind| {
+                diagnostics
+                    .iter()
+                    .filter(move |diagnostic| diagnostic.kind == kind)
+            };
+            let unused: Vec<_> = of_kind(DiagnosticKind::Unused)
+                .map(|diagnostic| diagnostic.name.as_str())
+                .collect();
+            if !unused.is_empty() && verbose {
+                let _ = writeln!(
+                    result,
+                    "{} constants of the table are not used:",
+                    unused.len()
+                );
+                for name in unused {
+                    let _ = writeln!(result, "    {}", name);
+                }
+            } else if !unused.is_empty() {
+                let _ = writeln!(
+                    result,
+                    "{} constants of the table are not used, run with `--verbose` to list them",
+                    unused.len()
+                );
+            }
+            for (kind, title) in [
+                (
+                    DiagnosticKind::Missing,
+                    "look like constants but are not in the table",
+                ),
+                (
+                    DiagnosticKind::Shadowed,
+                    "are declared in modules and shadow an entry of the table",
+                ),
+            ] {
+                let diagnostics: Vec<_> = of_kind(kind).collect();
+                if diagnostics.is_empty() {
+                    continue;
+                }
+                let _ = writeln!(result, "{} identifiers {}:", diagnostics.len(), title);
+                for diagnostic in diagnostics {
+                    let _ = writeln!(
+                        result,
+                        "    {}: {}",
+                        diagnostic.name,
+                        diagnostic.locations.join(", ")
+                    );
+                }
+            }
+            for (kind, title) in [
+                (
+                    DiagnosticKind::StaleComment,
+                    "comments disagree with the value of their constant",
+                ),
+                (
+                    DiagnosticKind::UndeclaredAddress,
+                    "constants use a named address their package does not declare, check that a dependency does",
+                ),
+            ] {
+                let diagnostics: Vec<_> = of_kind(kind).collect();
+                if diagnostics.is_empty() {
+                    continue;
+                }
+                let _ = writeln!(result, "{} {}:", diagnostics.len(), title);
+                for diagnostic in diagnostics {
+                    let _ = writeln!(
+                        result,
+                        "    {}: {}",
+                        diagnostic.name,
+                        diagnostic.detail.as_deref().unwrap_or_default()
+                    );
+                }
+            }
+            result
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+    use crate::const_values::{get_constant_values, table_from_str};
+    use crate::diagnostics::{
+        render_diagnostics, DiagnosticKind, Diagnostics, DiagnosticsFormat, Findings,
+    };
+    use crate::package::MovePackage;
+
+    #[test]
+    fn test_diagnostics_summary() {
+        let table = get_constant_values().unwrap();
+        let mut diagnostics = Diagnostics::default();
+        let module = "verifier_addr::fri_layer".to_string();
+        diagnostics.add(
+            "a.move",
+            Findings {
+                used: table
+                    .keys()
+                    .filter(|key| *key != "FRI_MAX_STEP_SIZE")
+                    .cloned()
+                    .collect(),
+                missing: vec![(module.clone(), "FRI_UNKNOWN".to_string())],
+                shadowed: vec![],
+                uses: vec![],
+            },
+        );
+        diagnostics.add(
+            "b.move",
+            Findings {
+                used: BTreeSet::new(),
+                missing: vec![(module.clone(), "FRI_UNKNOWN".to_string())],
+                shadowed: vec![(module, "MAX_N_QUERIES".to_string())],
+                uses: vec![],
+            },
+        );
+        let report = diagnostics.report(&table);
+        assert_eq!(
+            report.iter().map(|d| d.kind).collect::<Vec<_>>(),
+            vec![
+                DiagnosticKind::Unused,
+                DiagnosticKind::Missing,
+                DiagnosticKind::Shadowed
+            ]
+        );
+        assert_eq!(
+            render_diagnostics(&report, DiagnosticsFormat::Text, false),
+            "1 constants of the table are not used, run with `--verbose` to list them\n\
+             1 identifiers look like constants but are not in the table:\n\
+             \x20   FRI_UNKNOWN: a.move (verifier_addr::fri_layer), b.move (verifier_addr::fri_layer)\n\
+             1 identifiers are declared in modules and shadow an entry of the table:\n\
+             \x20   MAX_N_QUERIES: b.move (verifier_addr::fri_layer)\n"
+        );
+        assert!(
+            render_diagnostics(&report, DiagnosticsFormat::Text, true).starts_with(
+                "1 constants of the table are not used:\n\
+             \x20   FRI_MAX_STEP_SIZE\n\
+             1 identifiers"
+            )
+        );
+        let json: serde_json::Value =
+            serde_json::from_str(&render_diagnostics(&report, DiagnosticsFormat::Json, false))
+                .unwrap();
+        assert_eq!(json[1]["kind"], "missing");
+        assert_eq!(json[1]["locations"][1], "b.move (verifier_addr::fri_layer)");
+
+        let empty = Diagnostics::default().report(&HashMap::new());
+        assert_eq!(
+            render_diagnostics(&empty, DiagnosticsFormat::Text, false),
+            ""
+        );
+
+        // derived entries are not written in the table, they are never unused
+        let table = table_from_str(
+            r#"
+SMALL_PRIME = { type = "u64", value = "17" }
+HALF = { type = "u64", value = "1 / 2", field = "SMALL_PRIME", montgomery = true }
+"#,
+        )
+        .unwrap();
+        let mut diagnostics = Diagnostics::default();
+        diagnostics.add(
+            "a.move",
+            Findings {
+                used: BTreeSet::from(["SMALL_PRIME".to_string(), "HALF".to_string()]),
+                ..Findings::default()
+            },
+        );
+        assert!(diagnostics.report(&table).is_empty());
+    }
+
+    #[test]
+    fn test_stale_comments() {
+        let table = table_from_str(
+            r#"
+MAX_STEP = { type = "u64", value = "4" }
+COSET_SIZE = { type = "u64", value = "2 ** MAX_STEP", comment = "2^64" }
+N_QUERIES = { type = "u64", value = "48", comment = "0x20" }
+N_LAYERS = { type = "u64", value = "2 * MAX_STEP", comment = "8" }
+N_STEPS = { type = "u64", value = "MAX_STEP * 2", comment = "2 * MAX_STEP" }
+GROUP_SIZE = { type = "u64", value = "2 ** 11", comment = "2 ** 10" }
+LAST_STEP = { type = "u64", value = "MAX_STEP - 1", comment = "MAX_STEP - 2" }
+OFFSET = { type = "u64", value = "4", comment = "Offset of the first query" }
+"#,
+        )
+        .unwrap();
+        let stale: Vec<_> = Diagnostics::default()
+            .report(&table)
+            .into_iter()
+            .filter(|d| d.kind == DiagnosticKind::StaleComment)
+            .collect();
+        assert_eq!(
+            render_diagnostics(&stale, DiagnosticsFormat::Text, false),
+            "3 comments disagree with the value of their constant:\n\
+             \x20   GROUP_SIZE: the comment says 2 ** 10 = 0x400, the value is 0x800\n\
+             \x20   LAST_STEP: the comment says MAX_STEP - 2 = 0x2, the value is 0x3\n\
+             \x20   N_QUERIES: the comment says 0x20, the value is 0x30\n"
+        );
+    }
+
+    #[test]
+    fn test_undeclared_addresses() {
+        let table = table_from_str(
+            r#"
+OWNER = { type = "address", value = "@verifier_addr" }
+FRAMEWORK = { type = "address", value = "@aptos_framework" }
+"#,
+        )
+        .unwrap();
+        let package = MovePackage {
+            name: "verifier".to_string(),
+            root: "verifier".into(),
+            addresses: BTreeMap::from([("verifier_addr".to_string(), "_".to_string())]),
+            dev_addresses: BTreeMap::new(),
+        };
+        let mut diagnostics = Diagnostics::default();
+        diagnostics.add(
+            "a.move",
+            Findings {
+                used: table.keys().cloned().collect(),
+                ..Findings::default()
+            },
+        );
+        assert_eq!(
+            render_diagnostics(
+                &diagnostics.undeclared_addresses(&table, &package),
+                DiagnosticsFormat::Text,
+                false
+            ),
+            "1 constants use a named address their package does not declare, \
+             check that a dependency does:\n\
+             \x20   FRAMEWORK: @aptos_framework is not declared by package verifier\n"
+        );
+    }
+}
@@ -0,0 +1,231 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Write;
+
+use clap::ValueEnum;
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+
+use crate::const_values::{constant_name, constant_scope, scope_chain, ConstantValue};
+use crate::core_error::CoreError;
+use crate::move_type::MoveType;
+use crate::number_format::{Notation, NumberFormat};
+
+/// Key of the table of error codes, at the top of a table file or in a section:
+///
+/// ```toml
+/// [errors]
+/// EINVALID_INPUT = { code = 1, message = "The input is invalid" }
+///
+/// ["verifier_addr::fri_layer".errors]
+/// EINVALID_STEP_SIZE = { code = 2, message = "The FRI step size is not supported" }
+/// ```
+pub const ERRORS_KEY: &str = "errors";
+
+/// An abort code of a Move module, generated as an `#[error]` `u64` constant documented with
+/// its message.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ErrorCode {
+    pub code: u64,
+    pub message: String,
+}
+
+impl From<ErrorCode> for ConstantValue {
+    fn from(error: ErrorCode) -> Self {
+        ConstantValue {
+            r#type: MoveType::U64,
+            value: error.code.to_string(),
+            comment: None,
+            number_format: NumberFormat {
+                format: Some(Notation::Decimal),
+                ..Default::default()
+            },
+            expression: String::new(),
+            error_message: Some(error.message),
+            field: None,
+            montgomery: false,
+            derived: false,
+            resolved: vec![],
+        }
+    }
+}
+
+/// Fails if two error codes visible from the same scope have the same code: the codes of a
+/// module, and those of its address and the global ones it inherits. An error code redefined
+/// under the same name in a narrower scope may keep its code.
+pub fn check_unique_codes(table: &HashMap<String, ConstantValue>) -> Result<(), CoreError> {
+    let errors: BTreeMap<&str, &ConstantValue> = table
+        .iter()
+        .filter(|(_, constant)| constant.error_message.is_some())
+        .map(|(key, constant)| (key.as_str(), constant))
+        .collect();
+    let scopes: BTreeSet<_> = errors.keys().map(|key| constant_scope(key)).collect();
+    for scope in scopes {
+        // the most specific error code of each name visible from `scope`
+        let mut visible = BTreeMap::<&str, (&str, &ConstantValue)>::new();
+        for outer in scope_chain(scope) {
+            for (key, constant) in &errors {
+                if constant_scope(key) == outer {
+                    visible.entry(constant_name(key)).or_insert((key, constant));
+                }
+            }
+        }
+        let mut codes = BTreeMap::<&BigUint, Vec<&str>>::new();
+        for (key, constant) in visible.into_values() {
+            if let Some(code) = constant.resolved.first() {
+                codes.entry(code).or_default().push(key);
+            }
+        }
+        if let Some((code, mut keys)) = codes.into_iter().find(|(_, keys)| keys.len() > 1) {
+            keys.sort();
+            return Err(CoreError::DuplicateErrorCode {
+                code: code.to_string(),
+                names: keys.iter().map(|key| key.to_string()).collect(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Formats of the error catalogue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CatalogueFormat {
+    Json,
+    Markdown,
+}
+
+/// An error code of the catalogue, for off-chain clients decoding aborts.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CatalogueEntry {
+    /// Module or address the error is defined for, empty for every module
+    pub scope: String,
+    pub name: String,
+    pub code: u64,
+    pub message: String,
+}
+
+/// The error codes of `table`, sorted by scope and code.
+pub fn error_catalogue(table: &HashMap<String, ConstantValue>) -> Vec<CatalogueEntry> {
+    let mut entries: Vec<_> = table
+        .iter()
+        .filter_map(|(key, constant)| {
+            Some(CatalogueEntry {
+                scope: constant_scope(key).to_string(),
+                name: constant_name(key).to_string(),
+                code: constant.expression.parse().ok()?,
+                message: constant.error_message.clone()?,
+            })
+        })
+        .collect();
+    entries.sort_by(|a, b| (&a.scope, a.code, &a.name).cmp(&(&b.scope, b.code, &b.name)));
+    entries
+}
+
+pub fn render_catalogue(entries: &[CatalogueEntry], format: CatalogueFormat) -> String {
+    match format {
+        CatalogueFormat::Json => serde_json::to_string_pretty(entries).unwrap_or_default() + "\n",
+        CatalogueFormat::Markdown => {
+            let mut result = String::new();
+            let mut scope = None;
+            for entry in entries {
+                if scope != Some(&entry.scope) {
+                    let title = match entry.scope.as_str() {
+                        "" => "All modules",
+                        scope => scope,
+                    };
+                    if scope.is_some() {
+                        result += "\n";
+                    }
+                    let _ = writeln!(result, "## {}\n", title);
+                    result += "| Code | Name | Message |\n|---:|---|---|\n";
+                    scope = Some(&entry.scope);
+                }
+                let _ = writeln!(
+                    result,
+                    "| {} | `{}` | {} |",
+                    entry.code,
+                    entry.name,
+                    entry.message.replace('|', "\\|")
+                );
+            }
+            result
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use crate::const_values::ConstantValue;
+    use crate::core_error::CoreError;
+    use crate::error_codes::{
+        check_unique_codes, error_catalogue, render_catalogue, CatalogueFormat, ErrorCode,
+    };
+
+    fn error(code: u64, message: &str) -> ConstantValue {
+        let mut constant = ConstantValue::from(ErrorCode {
+            code,
+            message: message.to_string(),
+        });
+        constant.expression = constant.value.clone();
+        constant.resolved = vec![code.into()];
+        constant
+    }
+
+    #[test]
+    fn test_check_unique_codes() {
+        let mut table = HashMap::from([
+            ("EA".to_string(), error(1, "a")),
+            ("0x1::m::EA".to_string(), error(1, "a in m")),
+            ("0x1::m::EB".to_string(), error(2, "b")),
+            ("0x1::n::EC".to_string(), error(2, "c")),
+        ]);
+        // modules are independent, `EB` and `EC` are never visible together
+        assert!(check_unique_codes(&table).is_ok());
+        table.insert("0x1::ED".to_string(), error(2, "d"));
+        let err = check_unique_codes(&table).unwrap_err();
+        assert!(matches!(err, CoreError::DuplicateErrorCode { .. }));
+        assert_eq!(
+            err.to_string(),
+            "error codes 0x1::ED, 0x1::m::EB have the same code 2"
+        );
+        table.remove("0x1::ED");
+        // codes are compared by value, not by how they are written
+        let mut other = error(1, "e");
+        other.expression = "0x1".to_string();
+        table.insert("0x1::n::EE".to_string(), other);
+        assert_eq!(
+            check_unique_codes(&table).unwrap_err().to_string(),
+            "error codes 0x1::n::EE, EA have the same code 1"
+        );
+    }
+
+    #[test]
+    fn test_render_catalogue() {
+        let table = HashMap::from([
+            (
+                "EINVALID_INPUT".to_string(),
+                error(1, "The input is invalid"),
+            ),
+            (
+                "verifier_addr::fri_layer::EINVALID_STEP".to_string(),
+                error(2, "Step is not | supported"),
+            ),
+        ]);
+        let entries = error_catalogue(&table);
+        assert_eq!(
+            render_catalogue(&entries, CatalogueFormat::Markdown),
+            "## All modules\n\n\
+             | Code | Name | Message |\n|---:|---|---|\n\
+             | 1 | `EINVALID_INPUT` | The input is invalid |\n\
+             \n## verifier_addr::fri_layer\n\n\
+             | Code | Name | Message |\n|---:|---|---|\n\
+             | 2 | `EINVALID_STEP` | Step is not \\| supported |\n"
+        );
+        let json: serde_json::Value =
+            serde_json::from_str(&render_catalogue(&entries, CatalogueFormat::Json)).unwrap();
+        assert_eq!(json[1]["scope"], "verifier_addr::fri_layer");
+        assert_eq!(json[1]["code"], 2);
+    }
+}
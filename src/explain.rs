@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use num_bigint::BigUint;
+
+use crate::const_values::{constant_dependencies, constant_name, ConstantValue};
+use crate::expression::Expression;
+
+/// Keys of the entries of `table` named `name`, either a key or a name in any scope, sorted.
+pub fn find_constants<'a>(
+    table: &'a HashMap<String, ConstantValue>,
+    name: &str,
+) -> Vec<&'a String> {
+    let mut keys: Vec<_> = table
+        .keys()
+        .filter(|key| *key == name || constant_name(key) == name)
+        .collect();
+    keys.sort();
+    keys
+}
+
+/// Describes how the constant `key` of `table` is derived: its type and expression, the tree
+/// of the constants it depends on, its value and `uses`, the files and modules using it.
+pub fn explain_constant(
+    table: &HashMap<String, ConstantValue>,
+    key: &str,
+    uses: &[String],
+) -> String {
+    let mut result = String::new();
+    let Some(constant) = table.get(key) else {
+        return result;
+    };
+    let _ = writeln!(result, "{}: {}", key, constant.r#type);
+    let _ = writeln!(result, "expression: {}", constant.expression.trim());
+    if let Some(message) = &constant.error_message {
+        let _ = writeln!(result, "message: {}", message);
+    }
+    let dependencies = constant_dependencies(table, key);
+    if dependencies.is_empty() {
+        result += "depends on no other constant\n";
+    } else {
+        result += "depends on:\n";
+        for dependency in dependencies {
+            write_dependency(&mut result, table, dependency, 1);
+        }
+    }
+    match constant.resolved.as_slice() {
+        [] => {
+            let _ = writeln!(result, "value: {}", constant.value);
+        }
+        values => {
+            let decimal = render_values(constant, values, |value| value.to_string());
+            let hex = render_values(constant, values, |value| format!("0x{:x}", value));
+            let _ = writeln!(result, "value: {} ({})", decimal, hex);
+        }
+    }
+    if uses.is_empty() {
+        result += "not used by any Move file\n";
+    } else {
+        result += "used in:\n";
+        for location in uses {
+            let _ = writeln!(result, "    {}", location);
+        }
+    }
+    result
+}
+
+/// Writes `key` with its expression and value, then the constants it depends on below it.
+fn write_dependency(
+    result: &mut String,
+    table: &HashMap<String, ConstantValue>,
+    key: &str,
+    depth: usize,
+) {
+    let constant = &table[key];
+    let expression = constant.expression.trim();
+    let indent = "    ".repeat(depth);
+    let is_literal = matches!(Expression::parse(expression), Ok(Expression::Number(_)));
+    if is_literal || expression == constant.value {
+        let _ = writeln!(result, "{}{} = {}", indent, key, constant.value);
+    } else {
+        let _ = writeln!(
+            result,
+            "{}{} = {} = {}",
+            indent, key, expression, constant.value
+        );
+    }
+    for dependency in constant_dependencies(table, key) {
+        write_dependency(result, table, dependency, depth + 1);
+    }
+}
+
+fn render_values(
+    constant: &ConstantValue,
+    values: &[BigUint],
+    render: impl Fn(&BigUint) -> String,
+) -> String {
+    let values: Vec<_> = values.iter().map(render).collect();
+    match constant.r#type.bits() {
+        Some(_) => values.join(", "),
+        None => format!("[{}]", values.join(", ")),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::const_values::table_from_str;
+    use crate::explain::{explain_constant, find_constants};
+
+    #[test]
+    fn test_explain_constant() {
+        let table = table_from_str(
+            r#"
+FRI_MAX_STEP_SIZE = { type = "u64", value = "4" }
+MAX_COSET_SIZE = { type = "u64", value = "1 << FRI_MAX_STEP_SIZE" }
+GROUP_SIZE = { type = "u64", value = "32 * MAX_COSET_SIZE", comment = "Size of the group" }
+STEPS = { type = "vector<u8>", value = "[FRI_MAX_STEP_SIZE, 2]" }
+
+["verifier_addr::fri_layer"]
+GROUP_SIZE = { type = "u64", value = "2 * GROUP_SIZE" }
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            find_constants(&table, "GROUP_SIZE"),
+            vec!["GROUP_SIZE", "verifier_addr::fri_layer::GROUP_SIZE"]
+        );
+        assert_eq!(
+            find_constants(&table, "verifier_addr::fri_layer::GROUP_SIZE"),
+            vec!["verifier_addr::fri_layer::GROUP_SIZE"]
+        );
+        assert!(find_constants(&table, "UNKNOWN").is_empty());
+
+        let uses = vec!["sources/fri_layer.move (verifier_addr::fri_layer)".to_string()];
+        assert_eq!(
+            explain_constant(&table, "verifier_addr::fri_layer::GROUP_SIZE", &uses),
+            "verifier_addr::fri_layer::GROUP_SIZE: u64\n\
+             expression: 2 * GROUP_SIZE\n\
+             depends on:\n\
+             \x20   GROUP_SIZE = 32 * MAX_COSET_SIZE = 0x200\n\
+             \x20       MAX_COSET_SIZE = 1 << FRI_MAX_STEP_SIZE = 0x10\n\
+             \x20           FRI_MAX_STEP_SIZE = 0x4\n\
+             value: 1024 (0x400)\n\
+             used in:\n\
+             \x20   sources/fri_layer.move (verifier_addr::fri_layer)\n"
+        );
+        assert_eq!(
+            explain_constant(&table, "STEPS", &[]),
+            "STEPS: vector<u8>\n\
+             expression: [FRI_MAX_STEP_SIZE, 2]\n\
+             depends on:\n\
+             \x20   FRI_MAX_STEP_SIZE = 0x4\n\
+             value: [4, 2] ([0x4, 0x2])\n\
+             not used by any Move file\n"
+        );
+    }
+}
@@ -0,0 +1,940 @@
+use std::fmt;
+
+use num_bigint::BigUint;
+use num_traits::{One, ToPrimitive, Zero};
+use sha2::Sha256;
+use sha3::{Digest, Keccak256, Sha3_256};
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum ExpressionError {
+    #[error("unexpected character '{0}'")]
+    UnexpectedChar(char),
+
+    #[error("invalid number literal {0}")]
+    InvalidNumber(String),
+
+    #[error("unexpected token {0}")]
+    UnexpectedToken(String),
+
+    #[error("unexpected end of expression")]
+    UnexpectedEnd,
+
+    #[error("unknown identifier {0}")]
+    UnknownIdentifier(String),
+
+    #[error("{0} overflows u{1}")]
+    Overflow(&'static str, u32),
+
+    #[error("subtraction underflow")]
+    Underflow,
+
+    #[error("division by zero")]
+    DivisionByZero,
+
+    #[error("shift amount {0} must be less than {1}")]
+    ShiftTooLarge(BigUint, u32),
+
+    #[error("unknown function {0}")]
+    UnknownFunction(String),
+
+    #[error("{function} takes {expected} arguments, {found} given")]
+    WrongArgumentCount {
+        function: &'static str,
+        expected: usize,
+        found: usize,
+    },
+
+    #[error("{0}: {1}")]
+    InvalidArgument(&'static str, String),
+
+    #[error("byte string {0} is not an integer, it can only be hashed")]
+    NotAnInteger(String),
+
+    #[error("{0} is not a field operation")]
+    NotAFieldOperation(&'static str),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    /// `~`, `!`
+    Not,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Pow,
+    Shl,
+    Shr,
+    And,
+    Or,
+    Xor,
+}
+
+impl BinaryOp {
+    fn from_symbol(symbol: &str) -> Option<Self> {
+        Some(match symbol {
+            "+" => BinaryOp::Add,
+            "-" => BinaryOp::Sub,
+            "*" => BinaryOp::Mul,
+            "/" | "//" => BinaryOp::Div,
+            "%" => BinaryOp::Mod,
+            "**" => BinaryOp::Pow,
+            "<<" => BinaryOp::Shl,
+            ">>" => BinaryOp::Shr,
+            "&" => BinaryOp::And,
+            "|" => BinaryOp::Or,
+            "^" => BinaryOp::Xor,
+            _ => return None,
+        })
+    }
+
+    fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Mod => "%",
+            BinaryOp::Pow => "**",
+            BinaryOp::Shl => "<<",
+            BinaryOp::Shr => ">>",
+            BinaryOp::And => "&",
+            BinaryOp::Or => "|",
+            BinaryOp::Xor => "^",
+        }
+    }
+
+    /// Left and right binding powers, the operator precedence follows the one of `math-parse`
+    /// which was used to evaluate the tables before, with `**` binding the tightest.
+    fn binding_power(&self) -> (u8, u8) {
+        match self {
+            BinaryOp::Or => (1, 2),
+            BinaryOp::Xor => (3, 4),
+            BinaryOp::And => (5, 6),
+            BinaryOp::Shl | BinaryOp::Shr => (7, 8),
+            BinaryOp::Add | BinaryOp::Sub => (9, 10),
+            BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => (11, 12),
+            BinaryOp::Pow => (14, 13),
+        }
+    }
+}
+
+const PREFIX_BINDING_POWER: u8 = 13;
+
+/// Built-in functions of the expressions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Function {
+    /// `keccak256(bytes)`, the hash read as a big-endian integer
+    Keccak256,
+    /// `sha2_256(bytes)`
+    Sha2_256,
+    /// `sha3_256(bytes)`
+    Sha3_256,
+    /// `log2(x)`, rounded down
+    Log2,
+    /// `pow(a, b)`, same as `a ** b`
+    Pow,
+    /// `modpow(g, e, p)`, `g ** e % p` without the intermediate overflow
+    ModPow,
+    /// `inverse(x, p)`, the inverse of `x` modulo `p`
+    Inverse,
+    /// `bit_length(x)`, the number of bits needed to write `x`
+    BitLength,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "keccak256" => Function::Keccak256,
+            "sha2_256" => Function::Sha2_256,
+            "sha3_256" => Function::Sha3_256,
+            "log2" => Function::Log2,
+            "pow" => Function::Pow,
+            "modpow" => Function::ModPow,
+            "inverse" => Function::Inverse,
+            "bit_length" => Function::BitLength,
+            _ => return None,
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Function::Keccak256 => "keccak256",
+            Function::Sha2_256 => "sha2_256",
+            Function::Sha3_256 => "sha3_256",
+            Function::Log2 => "log2",
+            Function::Pow => "pow",
+            Function::ModPow => "modpow",
+            Function::Inverse => "inverse",
+            Function::BitLength => "bit_length",
+        }
+    }
+
+    fn arity(&self) -> usize {
+        match self {
+            Function::Keccak256
+            | Function::Sha2_256
+            | Function::Sha3_256
+            | Function::Log2
+            | Function::BitLength => 1,
+            Function::Pow | Function::Inverse => 2,
+            Function::ModPow => 3,
+        }
+    }
+
+    fn is_hash(&self) -> bool {
+        matches!(
+            self,
+            Function::Keccak256 | Function::Sha2_256 | Function::Sha3_256
+        )
+    }
+}
+
+/// A constant expression, e.g. `(1 << FRI_MAX_STEP_SIZE) - 1`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Number(BigUint),
+    Identifier(String),
+    /// A string `"..."`, byte string `b"..."` or hex string `x"..."`, only valid as the
+    /// argument of a hash function
+    Bytes(Vec<u8>),
+    Unary(UnaryOp, Box<Expression>),
+    Binary(BinaryOp, Box<Expression>, Box<Expression>),
+    Call(Function, Vec<Expression>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(BigUint),
+    Identifier(String),
+    Bytes(Vec<u8>),
+    Operator(&'static str),
+    LeftParen,
+    RightParen,
+    Comma,
+}
+
+const OPERATORS: [&str; 14] = [
+    "**", "//", "<<", ">>", "+", "-", "*", "/", "%", "&", "|", "^", "~", "!",
+];
+
+fn tokenize(input: &str) -> Result<Vec<Token>, ExpressionError> {
+    let mut tokens = vec![];
+    let mut rest = input;
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        if c == '(' || c == ')' || c == ',' {
+            tokens.push(match c {
+                '(' => Token::LeftParen,
+                ')' => Token::RightParen,
+                _ => Token::Comma,
+            });
+            rest = &rest[1..];
+            continue;
+        }
+        let quote = match rest.as_bytes() {
+            [b'"', ..] => Some(0),
+            [b'b' | b'x', b'"', ..] => Some(1),
+            _ => None,
+        };
+        if let Some(quote) = quote {
+            let (bytes, end) = parse_bytes(rest, quote)?;
+            tokens.push(Token::Bytes(bytes));
+            rest = &rest[end..];
+            continue;
+        }
+        if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+            tokens.push(Token::Operator(op));
+            rest = &rest[op.len()..];
+            continue;
+        }
+        if c.is_ascii_alphanumeric() || c == '_' {
+            let end = rest
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .unwrap_or(rest.len());
+            let word = &rest[..end];
+            tokens.push(if c.is_ascii_digit() {
+                Token::Number(parse_number(word)?)
+            } else {
+                Token::Identifier(word.to_string())
+            });
+            rest = &rest[end..];
+            continue;
+        }
+        return Err(ExpressionError::UnexpectedChar(c));
+    }
+    Ok(tokens)
+}
+
+/// Parses a Move integer literal: decimal or `0x` hexadecimal, with optional `_` separators
+/// and an optional type suffix such as `u64`.
+fn parse_number(word: &str) -> Result<BigUint, ExpressionError> {
+    let invalid = || ExpressionError::InvalidNumber(word.to_string());
+    let (digits, radix) = match word.strip_prefix("0x") {
+        Some(hex) => (hex, 16),
+        None => (word, 10),
+    };
+    let digits = ["u8", "u16", "u32", "u64", "u128", "u256"]
+        .iter()
+        .find_map(|suffix| digits.strip_suffix(suffix))
+        .unwrap_or(digits)
+        .replace('_', "");
+    if digits.is_empty() {
+        return Err(invalid());
+    }
+    BigUint::parse_bytes(digits.as_bytes(), radix).ok_or_else(invalid)
+}
+
+/// Parses the string starting with `"` at `quote` in `input`, `x"..."` being hexadecimal and
+/// the others supporting the escapes of Move byte strings. Returns the bytes and the end of
+/// the string.
+fn parse_bytes(input: &str, quote: usize) -> Result<(Vec<u8>, usize), ExpressionError> {
+    let close = input[quote + 1..]
+        .char_indices()
+        .scan(false, |escaped, (i, c)| {
+            let is_end = c == '"' && !*escaped;
+            *escaped = c == '\\' && !*escaped;
+            Some((i, is_end))
+        })
+        .find(|(_, is_end)| *is_end)
+        .map(|(i, _)| quote + 1 + i)
+        .ok_or(ExpressionError::UnexpectedEnd)?;
+    let literal = &input[..=close];
+    let content = &input[quote + 1..close];
+    let invalid = || ExpressionError::InvalidNumber(literal.to_string());
+    if input.starts_with('x') {
+        if !content.is_ascii() || !content.len().is_multiple_of(2) {
+            return Err(invalid());
+        }
+        let bytes = (0..content.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&content[i..i + 2], 16).map_err(|_| invalid()))
+            .collect::<Result<_, _>>()?;
+        return Ok((bytes, close + 1));
+    }
+    let mut bytes = vec![];
+    let mut chars = content.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buffer = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+            continue;
+        }
+        bytes.push(match chars.next() {
+            Some('n') => b'\n',
+            Some('r') => b'\r',
+            Some('t') => b'\t',
+            Some('0') => 0,
+            Some(c @ ('\\' | '"')) => c as u8,
+            Some('x') => {
+                let digits: String = chars.by_ref().take(2).collect();
+                u8::from_str_radix(&digits, 16).map_err(|_| invalid())?
+            }
+            _ => return Err(invalid()),
+        });
+    }
+    Ok((bytes, close + 1))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn parse_expression(&mut self, min_binding_power: u8) -> Result<Expression, ExpressionError> {
+        let mut lhs = self.parse_prefix()?;
+        while let Some(Token::Operator(symbol)) = self.peek() {
+            let op = BinaryOp::from_symbol(symbol)
+                .ok_or_else(|| ExpressionError::UnexpectedToken(symbol.to_string()))?;
+            let (left_binding_power, right_binding_power) = op.binding_power();
+            if left_binding_power < min_binding_power {
+                break;
+            }
+            self.next();
+            let rhs = self.parse_expression(right_binding_power)?;
+            lhs = Expression::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_prefix(&mut self) -> Result<Expression, ExpressionError> {
+        match self.next() {
+            None => Err(ExpressionError::UnexpectedEnd),
+            Some(Token::Number(value)) => Ok(Expression::Number(value)),
+            Some(Token::Identifier(name)) if self.peek() == Some(&Token::LeftParen) => {
+                self.parse_call(name)
+            }
+            Some(Token::Identifier(name)) => Ok(Expression::Identifier(name)),
+            Some(Token::Bytes(bytes)) => Ok(Expression::Bytes(bytes)),
+            Some(Token::Operator("~" | "!")) => Ok(Expression::Unary(
+                UnaryOp::Not,
+                Box::new(self.parse_expression(PREFIX_BINDING_POWER)?),
+            )),
+            Some(Token::Operator("+")) => self.parse_expression(PREFIX_BINDING_POWER),
+            Some(Token::LeftParen) => {
+                let expression = self.parse_expression(0)?;
+                match self.next() {
+                    Some(Token::RightParen) => Ok(expression),
+                    Some(token) => Err(ExpressionError::UnexpectedToken(format!("{:?}", token))),
+                    None => Err(ExpressionError::UnexpectedEnd),
+                }
+            }
+            Some(token) => Err(ExpressionError::UnexpectedToken(format!("{:?}", token))),
+        }
+    }
+
+    /// Parses the arguments of a call to `name`, from its opening parenthesis.
+    fn parse_call(&mut self, name: String) -> Result<Expression, ExpressionError> {
+        let function = Function::from_name(&name).ok_or(ExpressionError::UnknownFunction(name))?;
+        self.next();
+        let mut arguments = vec![];
+        if self.peek() == Some(&Token::RightParen) {
+            self.next();
+        } else {
+            loop {
+                arguments.push(self.parse_expression(0)?);
+                match self.next() {
+                    Some(Token::Comma) => {}
+                    Some(Token::RightParen) => break,
+                    Some(token) => {
+                        return Err(ExpressionError::UnexpectedToken(format!("{:?}", token)))
+                    }
+                    None => return Err(ExpressionError::UnexpectedEnd),
+                }
+            }
+        }
+        if arguments.len() != function.arity() {
+            return Err(ExpressionError::WrongArgumentCount {
+                function: function.name(),
+                expected: function.arity(),
+                found: arguments.len(),
+            });
+        }
+        Ok(Expression::Call(function, arguments))
+    }
+}
+
+impl Expression {
+    pub fn parse(input: &str) -> Result<Self, ExpressionError> {
+        let mut parser = Parser {
+            tokens: tokenize(input)?,
+            position: 0,
+        };
+        let expression = parser.parse_expression(0)?;
+        match parser.next() {
+            None => Ok(expression),
+            Some(token) => Err(ExpressionError::UnexpectedToken(format!("{:?}", token))),
+        }
+    }
+
+    /// Names referenced by the expression, in order of appearance.
+    pub fn identifiers(&self) -> Vec<&str> {
+        match self {
+            Expression::Number(_) | Expression::Bytes(_) => vec![],
+            Expression::Identifier(name) => vec![name.as_str()],
+            Expression::Unary(_, operand) => operand.identifiers(),
+            Expression::Binary(_, lhs, rhs) => {
+                let mut result = lhs.identifiers();
+                result.extend(rhs.identifiers());
+                result
+            }
+            Expression::Call(_, arguments) => arguments
+                .iter()
+                .flat_map(|argument| argument.identifiers())
+                .collect(),
+        }
+    }
+
+    /// Evaluates the expression with unsigned integers of `bits` width.
+    ///
+    /// Like in Move, arithmetic aborts on overflow and shifts drop the bits shifted out.
+    /// Arguments of functions are evaluated as `u256`, only their result has to fit.
+    pub fn evaluate<F>(&self, bits: u32, lookup: &F) -> Result<BigUint, ExpressionError>
+    where
+        F: Fn(&str) -> Option<BigUint>,
+    {
+        let max = (BigUint::one() << bits) - 1u8;
+        let check = |op: &'static str, value: BigUint| {
+            if value > max {
+                Err(ExpressionError::Overflow(op, bits))
+            } else {
+                Ok(value)
+            }
+        };
+        match self {
+            Expression::Number(value) => check("literal", value.clone()),
+            Expression::Identifier(name) => match lookup(name) {
+                Some(value) => check("value", value),
+                None => Err(ExpressionError::UnknownIdentifier(name.clone())),
+            },
+            Expression::Bytes(bytes) => Err(ExpressionError::NotAnInteger(format!(
+                "x\"{}\"",
+                bytes
+                    .iter()
+                    .map(|b| format!("{:02x}", b))
+                    .collect::<String>()
+            ))),
+            Expression::Unary(UnaryOp::Not, operand) => Ok(&max ^ operand.evaluate(bits, lookup)?),
+            Expression::Call(function, arguments) => {
+                check(function.name(), function.call(arguments, lookup)?)
+            }
+            Expression::Binary(op, lhs, rhs) => {
+                let lhs = lhs.evaluate(bits, lookup)?;
+                let rhs = rhs.evaluate(bits, lookup)?;
+                let shift_amount = || match rhs.to_u32() {
+                    Some(amount) if amount < bits => Ok(amount),
+                    _ => Err(ExpressionError::ShiftTooLarge(rhs.clone(), bits)),
+                };
+                match op {
+                    BinaryOp::Add => check(op.symbol(), lhs + rhs),
+                    BinaryOp::Sub if rhs > lhs => Err(ExpressionError::Underflow),
+                    BinaryOp::Sub => Ok(lhs - rhs),
+                    BinaryOp::Mul => check(op.symbol(), lhs * rhs),
+                    BinaryOp::Div | BinaryOp::Mod if rhs.is_zero() => {
+                        Err(ExpressionError::DivisionByZero)
+                    }
+                    BinaryOp::Div => Ok(lhs / rhs),
+                    BinaryOp::Mod => Ok(lhs % rhs),
+                    BinaryOp::Pow if lhs <= BigUint::one() => {
+                        Ok(if rhs.is_zero() { BigUint::one() } else { lhs })
+                    }
+                    BinaryOp::Pow => match rhs.to_u32() {
+                        Some(exponent) if exponent <= bits => check(op.symbol(), lhs.pow(exponent)),
+                        _ => Err(ExpressionError::Overflow(op.symbol(), bits)),
+                    },
+                    BinaryOp::Shl => Ok((lhs << shift_amount()?) & &max),
+                    BinaryOp::Shr => Ok(lhs >> shift_amount()?),
+                    BinaryOp::And => Ok(lhs & rhs),
+                    BinaryOp::Or => Ok(lhs | rhs),
+                    BinaryOp::Xor => Ok(lhs ^ rhs),
+                }
+            }
+        }
+    }
+}
+
+impl Expression {
+    /// The expression with the identifiers `replacement` knows replaced, e.g. to expand the
+    /// constants it references.
+    pub fn substitute<F>(&self, replacement: &F) -> Expression
+    where
+        F: Fn(&str) -> Option<Expression>,
+    {
+        match self {
+            Expression::Identifier(name) => replacement(name).unwrap_or_else(|| self.clone()),
+            Expression::Number(_) | Expression::Bytes(_) => self.clone(),
+            Expression::Unary(op, operand) => {
+                Expression::Unary(*op, Box::new(operand.substitute(replacement)))
+            }
+            Expression::Binary(op, lhs, rhs) => Expression::Binary(
+                *op,
+                Box::new(lhs.substitute(replacement)),
+                Box::new(rhs.substitute(replacement)),
+            ),
+            Expression::Call(function, arguments) => Expression::Call(
+                *function,
+                arguments
+                    .iter()
+                    .map(|argument| argument.substitute(replacement))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Evaluates the expression in the prime field of order `modulus`: `/` multiplies by the
+    /// inverse and `-` wraps around. Exponents and the arguments of functions are evaluated
+    /// as `u256` integers, only their result is reduced.
+    pub fn evaluate_in_field<F>(
+        &self,
+        modulus: &BigUint,
+        lookup: &F,
+    ) -> Result<BigUint, ExpressionError>
+    where
+        F: Fn(&str) -> Option<BigUint>,
+    {
+        match self {
+            Expression::Number(value) => Ok(value % modulus),
+            Expression::Identifier(name) => match lookup(name) {
+                Some(value) => Ok(value % modulus),
+                None => Err(ExpressionError::UnknownIdentifier(name.clone())),
+            },
+            Expression::Bytes(_) => self.evaluate(256, lookup),
+            Expression::Call(function, arguments) => {
+                Ok(function.call(arguments, lookup)? % modulus)
+            }
+            Expression::Unary(UnaryOp::Not, _) => Err(ExpressionError::NotAFieldOperation("~")),
+            Expression::Binary(BinaryOp::Pow, lhs, rhs) => {
+                let base = lhs.evaluate_in_field(modulus, lookup)?;
+                Ok(base.modpow(&rhs.evaluate(256, lookup)?, modulus))
+            }
+            Expression::Binary(op, lhs, rhs) => {
+                let lhs = lhs.evaluate_in_field(modulus, lookup)?;
+                let rhs = rhs.evaluate_in_field(modulus, lookup)?;
+                match op {
+                    BinaryOp::Add => Ok((lhs + rhs) % modulus),
+                    BinaryOp::Sub => Ok((lhs + modulus - rhs) % modulus),
+                    BinaryOp::Mul => Ok(lhs * rhs % modulus),
+                    BinaryOp::Div if rhs.is_zero() => Err(ExpressionError::DivisionByZero),
+                    BinaryOp::Div => {
+                        let inverse = rhs.modinv(modulus).ok_or_else(|| {
+                            ExpressionError::InvalidArgument(
+                                "inverse",
+                                format!("{} has no inverse modulo {}", rhs, modulus),
+                            )
+                        })?;
+                        Ok(lhs * inverse % modulus)
+                    }
+                    op => Err(ExpressionError::NotAFieldOperation(op.symbol())),
+                }
+            }
+        }
+    }
+}
+
+/// Writes the expression back as source, with only the parentheses the precedence needs.
+/// Numbers of 32 bits or more are written in hexadecimal.
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expression::Number(value) if value.bits() > 32 => write!(f, "0x{:x}", value),
+            Expression::Number(value) => write!(f, "{}", value),
+            Expression::Identifier(name) => f.write_str(name),
+            Expression::Bytes(bytes)
+                if bytes
+                    .iter()
+                    .all(|b| b.is_ascii_graphic() && *b != b'"' && *b != b'\\' || *b == b' ') =>
+            {
+                write!(f, "\"{}\"", String::from_utf8_lossy(bytes))
+            }
+            Expression::Bytes(bytes) => {
+                f.write_str("x\"")?;
+                for byte in bytes {
+                    write!(f, "{:02x}", byte)?;
+                }
+                f.write_str("\"")
+            }
+            Expression::Unary(UnaryOp::Not, operand) => match operand.as_ref() {
+                Expression::Binary(..) => write!(f, "~({})", operand),
+                operand => write!(f, "~{}", operand),
+            },
+            Expression::Binary(op, lhs, rhs) => {
+                let (power, _) = op.binding_power();
+                let is_right_associative = *op == BinaryOp::Pow;
+                let needs_parentheses = |operand: &Expression, is_left: bool| match operand {
+                    Expression::Binary(inner, ..) => {
+                        let (inner_power, _) = inner.binding_power();
+                        inner_power < power
+                            || (inner_power == power && is_left == is_right_associative)
+                    }
+                    _ => false,
+                };
+                for (operand, is_left) in [(lhs, true), (rhs, false)] {
+                    if !is_left {
+                        write!(f, " {} ", op.symbol())?;
+                    }
+                    if needs_parentheses(operand, is_left) {
+                        write!(f, "({})", operand)?;
+                    } else {
+                        write!(f, "{}", operand)?;
+                    }
+                }
+                Ok(())
+            }
+            Expression::Call(function, arguments) => {
+                write!(f, "{}(", function.name())?;
+                for (i, argument) in arguments.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", argument)?;
+                }
+                f.write_str(")")
+            }
+        }
+    }
+}
+
+impl Function {
+    fn call<F>(&self, arguments: &[Expression], lookup: &F) -> Result<BigUint, ExpressionError>
+    where
+        F: Fn(&str) -> Option<BigUint>,
+    {
+        if self.is_hash() {
+            // integers are hashed as 32 bytes words, like `abi.encode(uint256)`
+            let bytes = match &arguments[0] {
+                Expression::Bytes(bytes) => bytes.clone(),
+                argument => {
+                    let bytes = argument.evaluate(256, lookup)?.to_bytes_be();
+                    let mut word = vec![0; 32 - bytes.len()];
+                    word.extend(bytes);
+                    word
+                }
+            };
+            let digest = match self {
+                Function::Keccak256 => Keccak256::digest(&bytes).to_vec(),
+                Function::Sha3_256 => Sha3_256::digest(&bytes).to_vec(),
+                _ => Sha256::digest(&bytes).to_vec(),
+            };
+            return Ok(BigUint::from_bytes_be(&digest));
+        }
+        let arguments = arguments
+            .iter()
+            .map(|argument| argument.evaluate(256, lookup))
+            .collect::<Result<Vec<_>, _>>()?;
+        let invalid =
+            |reason: &str| ExpressionError::InvalidArgument(self.name(), reason.to_string());
+        match (self, arguments.as_slice()) {
+            (Function::Log2, [x]) if x.is_zero() => Err(invalid("the logarithm of 0 is undefined")),
+            (Function::Log2, [x]) => Ok(BigUint::from(x.bits() - 1)),
+            (Function::BitLength, [x]) => Ok(BigUint::from(x.bits())),
+            (Function::Pow, [a, b]) => Expression::Binary(
+                BinaryOp::Pow,
+                Box::new(Expression::Number(a.clone())),
+                Box::new(Expression::Number(b.clone())),
+            )
+            .evaluate(256, lookup),
+            (Function::ModPow | Function::Inverse, [.., p]) if p.is_zero() => {
+                Err(ExpressionError::DivisionByZero)
+            }
+            (Function::ModPow, [g, e, p]) => Ok(g.modpow(e, p)),
+            (Function::Inverse, [x, p]) => x
+                .modinv(p)
+                .ok_or_else(|| invalid(&format!("{} has no inverse modulo {}", x, p))),
+            _ => unreachable!("the arity is checked while parsing"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use num_bigint::BigUint;
+
+    use crate::expression::{Expression, ExpressionError};
+
+    fn eval(input: &str, bits: u32) -> Result<BigUint, ExpressionError> {
+        let variables = HashMap::from([
+            ("FRI_MAX_STEP_SIZE", BigUint::from(4u8)),
+            (
+                "K_MODULUS",
+                BigUint::from(2u8).pow(251) + 17u8 * BigUint::from(2u8).pow(192) + 1u8,
+            ),
+        ]);
+        Expression::parse(input)?.evaluate(bits, &|name| variables.get(name).cloned())
+    }
+
+    fn hex(value: &str) -> BigUint {
+        BigUint::parse_bytes(value.as_bytes(), 16).unwrap()
+    }
+
+    #[test]
+    fn test_precedence() {
+        assert_eq!(eval("1 + 2 * 3", 64), Ok(7u8.into()));
+        assert_eq!(eval("(1 + 2) * 3", 64), Ok(9u8.into()));
+        assert_eq!(eval("1 << FRI_MAX_STEP_SIZE", 256), Ok(16u8.into()));
+        assert_eq!(eval("2 ** 3 ** 2", 64), Ok(512u16.into()));
+        assert_eq!(eval("6 ^ 3 & 1 | 8", 8), Ok(15u8.into()));
+        assert_eq!(eval("32 * 16 / 32", 64), Ok(16u8.into()));
+        assert_eq!(eval("10 % 4 // 1", 64), Ok(2u8.into()));
+    }
+
+    #[test]
+    fn test_u256_arithmetic() {
+        assert_eq!(
+            eval("K_MODULUS - 1", 256),
+            Ok(hex(
+                "800000000000011000000000000000000000000000000000000000000000000"
+            ))
+        );
+        assert_eq!(
+            eval("~0 << 96", 256),
+            Ok(hex(
+                "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF000000000000000000000000"
+            ))
+        );
+        assert_eq!(eval("2 ** 128 - 1", 256), Ok(BigUint::from(u128::MAX)));
+        assert_eq!(eval("0xff_ffu64 >> 8", 64), Ok(255u8.into()));
+    }
+
+    #[test]
+    fn test_type_width() {
+        assert_eq!(eval("255 + 1", 8), Err(ExpressionError::Overflow("+", 8)));
+        assert_eq!(
+            eval("2 ** 64", 64),
+            Err(ExpressionError::Overflow("**", 64))
+        );
+        assert_eq!(
+            eval("K_MODULUS", 128),
+            Err(ExpressionError::Overflow("value", 128))
+        );
+        assert_eq!(
+            eval("0x1ff", 8),
+            Err(ExpressionError::Overflow("literal", 8))
+        );
+        assert_eq!(eval("0xff << 4", 8), Ok(0xf0u8.into()));
+        assert_eq!(eval("~1", 8), Ok(0xfeu8.into()));
+        assert_eq!(
+            eval("1 << 8", 8),
+            Err(ExpressionError::ShiftTooLarge(8u8.into(), 8))
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(eval("1 - 2", 64), Err(ExpressionError::Underflow));
+        assert_eq!(eval("1 / 0", 64), Err(ExpressionError::DivisionByZero));
+        assert_eq!(
+            eval("UNKNOWN + 1", 64),
+            Err(ExpressionError::UnknownIdentifier("UNKNOWN".to_string()))
+        );
+        assert_eq!(eval("(1 + 2", 64), Err(ExpressionError::UnexpectedEnd));
+        assert_eq!(
+            eval(r#"b"STARKNET""#, 64),
+            Err(ExpressionError::NotAnInteger(
+                "x\"535441524b4e4554\"".to_string()
+            ))
+        );
+        assert_eq!(eval("1 @ 2", 64), Err(ExpressionError::UnexpectedChar('@')));
+    }
+
+    #[test]
+    fn test_functions() {
+        assert_eq!(
+            eval(r#"keccak256("")"#, 256),
+            Ok(hex(
+                "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+            ))
+        );
+        assert_eq!(
+            eval(r#"sha2_256(b"abc")"#, 256),
+            Ok(hex(
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+            ))
+        );
+        assert_eq!(
+            eval(r#"sha3_256(x"616263")"#, 256),
+            Ok(hex(
+                "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532"
+            ))
+        );
+        assert_eq!(eval("keccak256(0) >> 248", 256), Ok(0x29u8.into()));
+        assert_eq!(eval("log2(1 << FRI_MAX_STEP_SIZE)", 64), Ok(4u8.into()));
+        assert_eq!(eval("log2(17)", 64), Ok(4u8.into()));
+        assert_eq!(eval("bit_length(255) + bit_length(0)", 64), Ok(8u8.into()));
+        assert_eq!(eval("pow(2, FRI_MAX_STEP_SIZE)", 64), Ok(16u8.into()));
+        assert_eq!(eval("modpow(3, 4, 5)", 8), Ok(1u8.into()));
+        assert_eq!(eval("inverse(3, 7)", 8), Ok(5u8.into()));
+        assert_eq!(
+            eval("modpow(2, 256, K_MODULUS)", 256),
+            Ok(hex(
+                "7fffffffffffdf0ffffffffffffffffffffffffffffffffffffffffffffffe1"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        for (input, output) in [
+            ("(1 + 2) * 3", "(1 + 2) * 3"),
+            ("1 + (2 * 3)", "1 + 2 * 3"),
+            ("10 - (4 - 1)", "10 - (4 - 1)"),
+            ("(10 - 4) - 1", "10 - 4 - 1"),
+            ("(2 ** 3) ** 2", "(2 ** 3) ** 2"),
+            ("2 ** (3 ** 2)", "2 ** 3 ** 2"),
+            ("~(1 << 4)", "~(1 << 4)"),
+            ("modpow(3, (K - 1) / 8, K)", "modpow(3, (K - 1) / 8, K)"),
+            (r#"keccak256(b"A B")"#, r#"keccak256("A B")"#),
+            (r#"sha2_256(x"00ff")"#, r#"sha2_256(x"00ff")"#),
+            ("0x800000000000011", "0x800000000000011"),
+            ("0x20", "32"),
+        ] {
+            let expression = Expression::parse(input).unwrap();
+            assert_eq!(expression.to_string(), output);
+            assert_eq!(Expression::parse(output).unwrap(), expression);
+        }
+        let expression = Expression::parse("A * 2")
+            .unwrap()
+            .substitute(&|name| (name == "A").then(|| Expression::parse("B + 1").unwrap()));
+        assert_eq!(expression.to_string(), "(B + 1) * 2");
+    }
+
+    #[test]
+    fn test_field_arithmetic() {
+        let modulus = BigUint::from(17u8);
+        let eval = |input: &str| {
+            Expression::parse(input)
+                .unwrap()
+                .evaluate_in_field(&modulus, &|_| None)
+        };
+        assert_eq!(eval("3 - 5"), Ok(15u8.into()));
+        assert_eq!(eval("1 / 3"), Ok(6u8.into()));
+        assert_eq!(eval("16 * 16 + 20"), Ok(4u8.into()));
+        // exponents are integers, not field elements
+        assert_eq!(eval("3 ** (16 / 2)"), Ok(16u8.into()));
+        assert_eq!(eval("inverse(3, 17) * 3"), Ok(1u8.into()));
+        assert_eq!(eval("1 / 0"), Err(ExpressionError::DivisionByZero));
+        assert_eq!(
+            eval("1 << 2"),
+            Err(ExpressionError::NotAFieldOperation("<<"))
+        );
+    }
+
+    #[test]
+    fn test_function_errors() {
+        assert_eq!(
+            eval("keccak256(\"a\")", 64),
+            Err(ExpressionError::Overflow("keccak256", 64))
+        );
+        assert_eq!(
+            eval("sqrt(4)", 64),
+            Err(ExpressionError::UnknownFunction("sqrt".to_string()))
+        );
+        assert_eq!(
+            eval("modpow(2, 3)", 64),
+            Err(ExpressionError::WrongArgumentCount {
+                function: "modpow",
+                expected: 3,
+                found: 2
+            })
+        );
+        assert_eq!(
+            eval("inverse(2, 0)", 64),
+            Err(ExpressionError::DivisionByZero)
+        );
+        assert_eq!(
+            eval("inverse(2, 4)", 64),
+            Err(ExpressionError::InvalidArgument(
+                "inverse",
+                "2 has no inverse modulo 4".to_string()
+            ))
+        );
+        assert!(matches!(
+            eval("log2(0)", 64),
+            Err(ExpressionError::InvalidArgument("log2", _))
+        ));
+        assert_eq!(
+            eval("log2(b\"a\")", 64),
+            Err(ExpressionError::NotAnInteger("x\"61\"".to_string()))
+        );
+    }
+}
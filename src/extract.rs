@@ -0,0 +1,309 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+use crate::core_error::CoreError;
+use crate::gen_const::generated_block;
+use crate::move_parser::{Module, SourceFile, TokenKind};
+use crate::move_type::MoveType;
+
+/// A constant found in a Move file, either a getter `public fun NAME(): T { expr }` or a
+/// `const NAME: T = expr;` declaration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Definition {
+    pub name: String,
+    pub r#type: MoveType,
+    /// Expression with the getter calls replaced by the constant names
+    pub value: String,
+    /// Comment directly above the definition
+    pub comment: Option<String>,
+    pub file: String,
+    pub module: String,
+}
+
+impl Definition {
+    /// Where the constant is defined, e.g. `sources/fri_layer.move (verifier_addr::fri_layer)`.
+    pub fn location(&self) -> String {
+        format!("{} ({})", self.file, self.module)
+    }
+
+    fn is_same(&self, other: &Definition) -> bool {
+        self.r#type == other.r#type && self.value == other.value
+    }
+}
+
+/// Definitions of several files merged by constant name.
+#[derive(Debug, Default)]
+pub struct Extraction {
+    /// First definition of every constant
+    pub constants: BTreeMap<String, Definition>,
+    /// Constants defined identically in more than one module, with all their definitions
+    pub duplicates: BTreeMap<String, Vec<Definition>>,
+    /// Constants defined differently in more than one module, with all their definitions
+    pub conflicts: BTreeMap<String, Vec<Definition>>,
+}
+
+impl Extraction {
+    pub fn add(&mut self, definition: Definition) {
+        let Some(first) = self.constants.get(&definition.name) else {
+            self.constants.insert(definition.name.clone(), definition);
+            return;
+        };
+        let first = first.clone();
+        let name = definition.name.clone();
+        let (same, different) = (self.duplicates.remove(&name), self.conflicts.remove(&name));
+        let mut definitions = same.or(different).unwrap_or_else(|| vec![first.clone()]);
+        definitions.push(definition);
+        if definitions
+            .iter()
+            .all(|definition| definition.is_same(&first))
+        {
+            self.duplicates.insert(name, definitions);
+        } else {
+            self.conflicts.insert(name, definitions);
+        }
+    }
+
+    /// Renders the constants as a constant table, conflicting definitions other than the
+    /// first one are listed in comments above the entry.
+    pub fn to_toml(&self) -> String {
+        let mut result = String::new();
+        for (name, definition) in &self.constants {
+            for other in self.conflicts.get(name).into_iter().flatten().skip(1) {
+                let _ = writeln!(
+                    result,
+                    "# conflicting definition in {}: {} = {}",
+                    other.location(),
+                    other.r#type,
+                    other.value
+                );
+            }
+            // without a comment, the default one of the table applies
+            let comment = match &definition.comment {
+                Some(comment) => format!(", comment = {}", toml_string(comment)),
+                None => String::new(),
+            };
+            let _ = writeln!(
+                result,
+                "{} = {{ type = {}, value = {}{} }}",
+                name,
+                toml_string(&definition.r#type.to_string()),
+                toml_string(&definition.value),
+                comment
+            );
+        }
+        result
+    }
+}
+
+fn toml_string(value: &str) -> String {
+    toml::Value::String(value.to_string()).to_string()
+}
+
+/// Whether `name` follows the `SCREAMING_SNAKE_CASE` convention of constants.
+pub fn is_constant_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_uppercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Finds the constant getters and declarations of a Move file. Declarations in a constants
+/// block generated by `orn` are skipped, they already come from a constant table.
+pub fn extract_definitions(
+    file_content: &str,
+    file_name: &str,
+) -> Result<Vec<Definition>, CoreError> {
+    let file = SourceFile::parse(file_content)?;
+    let mut definitions = vec![];
+    for module in &file.modules {
+        let block = generated_block(&file, module);
+        let mut found = vec![];
+        for function in &module.functions {
+            let (Some((open, close)), Some(return_type)) = (function.body, &function.return_type)
+            else {
+                continue;
+            };
+            if !function.is_public || function.has_params || !is_constant_name(&function.name) {
+                continue;
+            }
+            // only getters made of a single expression
+            let is_expression =
+                !(open + 1..close).any(|i| matches!(file.text(i), ";" | "let" | "return"));
+            let Ok(r#type) = return_type.parse::<MoveType>() else {
+                continue;
+            };
+            if is_expression {
+                found.push((
+                    function.name.clone(),
+                    r#type,
+                    function.start,
+                    open + 1..close,
+                ));
+            }
+        }
+        for constant in &module.consts {
+            if block
+                .as_ref()
+                .is_some_and(|block| block.contains(&constant.name_token))
+            {
+                continue;
+            }
+            let Ok(r#type) = constant.r#type.parse::<MoveType>() else {
+                continue;
+            };
+            let Some(equal) = (constant.name_token..constant.end).find(|i| file.text(*i) == "=")
+            else {
+                continue;
+            };
+            found.push((
+                constant.name.clone(),
+                r#type,
+                constant.start,
+                equal + 1..constant.end,
+            ));
+        }
+        found.sort_by_key(|(_, _, start, _)| *start);
+        for (name, r#type, start, tokens) in found {
+            definitions.push(Definition {
+                name,
+                r#type,
+                value: normalize_expression(&file, module, tokens),
+                comment: comment_above(&file, start),
+                file: file_name.to_string(),
+                module: module.name.clone(),
+            });
+        }
+    }
+    Ok(definitions)
+}
+
+/// Source of the tokens on a single line, with comments dropped, getter calls such as
+/// `a::b::NAME()` replaced by `NAME` and casts `as u128` removed.
+fn normalize_expression(
+    file: &SourceFile,
+    module: &Module,
+    tokens: std::ops::Range<usize>,
+) -> String {
+    let references: HashMap<_, _> = module
+        .references
+        .iter()
+        .filter(|reference| tokens.contains(&reference.token))
+        .map(|reference| (reference.tokens().start, reference))
+        .collect();
+    let mut result = String::new();
+    let mut index = tokens.start;
+    while index < tokens.end {
+        let token = &file.tokens[index];
+        if let Some(reference) = references.get(&index) {
+            let name = file.text(reference.token);
+            if is_constant_name(name) {
+                result += name;
+                index = reference.tokens().end;
+                continue;
+            }
+        }
+        match token.kind {
+            TokenKind::Whitespace => {
+                if !result.is_empty() && !result.ends_with(' ') {
+                    result.push(' ');
+                }
+            }
+            TokenKind::LineComment | TokenKind::DocComment | TokenKind::BlockComment => {}
+            TokenKind::Identifier if token.text == "as" => {
+                // skip the type of the cast
+                if let Some(next) = file.next_significant(index) {
+                    index = next;
+                }
+                result.truncate(result.trim_end().len());
+            }
+            _ => result += token.text,
+        }
+        index += 1;
+    }
+    result.trim().to_string()
+}
+
+/// Comment lines directly above the token `start`, without the comment markers.
+fn comment_above(file: &SourceFile, start: usize) -> Option<String> {
+    let mut lines = vec![];
+    let mut index = start;
+    while index >= 2 {
+        let whitespace = &file.tokens[index - 1];
+        let comment = &file.tokens[index - 2];
+        if whitespace.kind != TokenKind::Whitespace
+            || whitespace.text.matches('\n').count() != 1
+            || !matches!(comment.kind, TokenKind::LineComment | TokenKind::DocComment)
+        {
+            break;
+        }
+        lines.push(comment.text.trim_start_matches('/').trim());
+        index -= 2;
+    }
+    lines.reverse();
+    let comment = lines.join(" ");
+    (!comment.is_empty()).then_some(comment)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::const_values::ConstantValue;
+    use crate::extract::{extract_definitions, Extraction};
+    use crate::move_type::MoveType;
+
+    #[test]
+    fn test_extract_definitions() {
+        let definitions = extract_definitions(
+            include_str!("./test_files/sample1_input.move"),
+            "fri_layer.move",
+        )
+        .unwrap();
+        let find = |name: &str| {
+            definitions
+                .iter()
+                .find(|definition| definition.name == name)
+                .unwrap()
+        };
+        let group_size = find("FRI_GROUP_SIZE");
+        assert_eq!(group_size.r#type, MoveType::U256);
+        assert_eq!(group_size.value, "0x20 * MAX_COSET_SIZE");
+        assert_eq!(group_size.module, "verifier_addr::fri_layer");
+        assert_eq!(
+            find("MAX_COSET_SIZE").value,
+            "(pow(2, (FRI_MAX_STEP_SIZE)))"
+        );
+    }
+
+    #[test]
+    fn test_duplicates_and_conflicts() {
+        let mut extraction = Extraction::default();
+        let sources = [
+            (
+                "a.move",
+                "module a::a {\n    /// Size of a slot\n    const SLOT_SIZE: u64 = 3;\n    public fun MASK(): u256 { 0xff }\n}\n",
+            ),
+            (
+                "b.move",
+                "module a::b {\n    public fun SLOT_SIZE(): u64 {\n        3\n    }\n    const MASK: u256 = 0xf;\n}\n",
+            ),
+        ];
+        for (file_name, source) in sources {
+            for definition in extract_definitions(source, file_name).unwrap() {
+                extraction.add(definition);
+            }
+        }
+        assert_eq!(extraction.constants.len(), 2);
+        assert_eq!(extraction.duplicates["SLOT_SIZE"].len(), 2);
+        assert_eq!(extraction.conflicts["MASK"].len(), 2);
+
+        let toml = extraction.to_toml();
+        assert_eq!(
+            toml,
+            "# conflicting definition in b.move (a::b): u256 = 0xf\n\
+             MASK = { type = \"u256\", value = \"0xff\" }\n\
+             SLOT_SIZE = { type = \"u64\", value = \"3\", comment = \"Size of a slot\" }\n"
+        );
+        let table: std::collections::HashMap<String, ConstantValue> =
+            toml::from_str(&toml).unwrap();
+        assert_eq!(table["SLOT_SIZE"].value, "3");
+    }
+}
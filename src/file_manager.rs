@@ -1,51 +1,518 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::io::{Read, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use glob::glob;
+use clap::ValueEnum;
+use glob::{glob, Pattern};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
 
 use crate::core_error::CoreError;
+use crate::package::MovePackage;
+
+/// Directories never searched for Move files: build outputs and dependency caches.
+pub const SKIPPED_DIRS: [&str; 3] = ["build", ".git", ".move"];
+/// Files with `.gitignore` rules excluding Move files.
+const IGNORE_FILES: [&str; 2] = [".gitignore", ".ornignore"];
+
+/// Journal of the last update, written in the working directory by `--backup journal`.
+pub const UNDO_JOURNAL: &str = ".orn-undo.json";
+
+/// How to keep the original content of the files an update overwrites.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum BackupMode {
+    /// Copy every updated file to `<file>.orig`
+    Orig,
+    /// Record the updated files in a journal `orn undo` restores
+    Journal,
+}
+
+/// Files changed by an update, with their content before and after it.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UndoJournal {
+    pub files: Vec<JournalEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub path: PathBuf,
+    pub original: String,
+    pub updated: String,
+}
+
+/// Files of a journal that [`FileManager::undo`] restored, and the ones it left alone because
+/// they were modified since the update.
+#[derive(Debug, Default, PartialEq)]
+pub struct UndoOutcome {
+    pub restored: Vec<PathBuf>,
+    pub skipped: Vec<PathBuf>,
+}
 
 pub struct FileManager {
-    files: HashMap<String, PathBuf>,
+    files: BTreeMap<String, PathBuf>,
+}
+
+/// Content of a file before and after running an updater on it.
+pub struct FileChange {
+    pub file_name: String,
+    pub path: PathBuf,
+    pub original: String,
+    pub updated: String,
+}
+
+impl FileChange {
+    pub fn is_changed(&self) -> bool {
+        self.original != self.updated
+    }
+
+    /// Lines removed from or inserted into the original content.
+    pub fn changed_lines(&self) -> Vec<String> {
+        TextDiff::from_lines(&self.original, &self.updated)
+            .iter_all_changes()
+            .filter(|change| change.tag() != ChangeTag::Equal)
+            .map(|change| change.value().to_string())
+            .collect()
+    }
+
+    /// Unified diff from the original to the updated content, empty if nothing changed.
+    pub fn unified_diff(&self) -> String {
+        TextDiff::from_lines(&self.original, &self.updated)
+            .unified_diff()
+            .context_radius(3)
+            .header(
+                &format!("a/{}", self.file_name),
+                &format!("b/{}", self.file_name),
+            )
+            .to_string()
+    }
 }
 
 impl FileManager {
     pub fn load(patterns: &Vec<String>) -> Result<Self, CoreError> {
-        let mut files = HashMap::<String, PathBuf>::new();
+        Self::load_with_excludes(patterns, &[])
+    }
+
+    /// Loads the files matching `patterns`, except the ones matching `excludes`, ignored by a
+    /// `.gitignore` or `.ornignore`, or in a directory of [`SKIPPED_DIRS`].
+    pub fn load_with_excludes(
+        patterns: &Vec<String>,
+        excludes: &[String],
+    ) -> Result<Self, CoreError> {
+        let excludes = excludes
+            .iter()
+            .map(|exclude| Pattern::new(exclude))
+            .collect::<Result<Vec<_>, _>>()?;
+        let current_dir = std::env::current_dir()?;
+        let mut ignore_files = IgnoreFiles::default();
+        let mut files = BTreeMap::<String, PathBuf>::new();
         for pattern in patterns {
+            let root = glob_root(pattern);
             for path in glob(pattern)? {
                 let path = path?;
                 // Check if the path is a file
                 if !path.is_file() {
                     continue;
                 }
+                let relative = path.strip_prefix(&current_dir).unwrap_or(&path);
+                // the directories the pattern names itself are never skipped
+                let below_root = path.strip_prefix(&root).unwrap_or(&path);
+                if excludes
+                    .iter()
+                    .any(|exclude| exclude.matches_path(relative))
+                    || in_skipped_dir(below_root)
+                    || ignore_files.is_ignored(&std::path::absolute(&path)?)
+                {
+                    continue;
+                }
                 files.insert(path.display().to_string(), path);
             }
         }
         Ok(Self { files })
     }
+
+    /// Loads the Move files of the source, test and script directories of `package`, with the
+    /// same exclusions as [`FileManager::load_with_excludes`].
+    pub fn load_package(package: &MovePackage, excludes: &[String]) -> Result<Self, CoreError> {
+        let patterns = package
+            .source_dirs()
+            .iter()
+            .map(|dir| dir.join("**").join("*.move").display().to_string())
+            .collect();
+        Self::load_with_excludes(&patterns, excludes)
+    }
+
+    /// Paths of the loaded files, sorted.
+    pub fn files(&self) -> impl Iterator<Item = &PathBuf> {
+        self.files.values()
+    }
+}
+
+/// Directory of the leading components of `pattern` without wildcards, e.g. `a/b` for
+/// `a/b/**/*.move`.
+fn glob_root(pattern: &str) -> PathBuf {
+    Path::new(pattern)
+        .components()
+        .take_while(|component| {
+            let component = component.as_os_str().to_string_lossy();
+            !component.contains(['*', '?', '['])
+        })
+        .collect()
+}
+
+/// Whether `path` is in a directory of [`SKIPPED_DIRS`].
+fn in_skipped_dir(path: &Path) -> bool {
+    let Some(parent) = path.parent() else {
+        return false;
+    };
+    parent
+        .components()
+        .any(|component| SKIPPED_DIRS.iter().any(|dir| component.as_os_str() == *dir))
+}
+
+/// Rules of the [`IGNORE_FILES`] of the directories visited so far.
+#[derive(Default)]
+struct IgnoreFiles {
+    rules: HashMap<PathBuf, Option<Gitignore>>,
+}
+
+impl IgnoreFiles {
+    /// Whether the absolute `path` is ignored by the ignore files of its directory or of a
+    /// parent, up to the root of the git repository. Rules of the closest directory win.
+    fn is_ignored(&mut self, path: &Path) -> bool {
+        for dir in path.ancestors().skip(1) {
+            let rules = self
+                .rules
+                .entry(dir.to_path_buf())
+                .or_insert_with(|| load_ignore_rules(dir));
+            if let Some(rules) = rules {
+                match rules.matched_path_or_any_parents(path, false) {
+                    Match::Ignore(_) => return true,
+                    Match::Whitelist(_) => return false,
+                    Match::None => {}
+                }
+            }
+            if dir.join(".git").exists() {
+                break;
+            }
+        }
+        false
+    }
+}
+
+fn load_ignore_rules(dir: &Path) -> Option<Gitignore> {
+    let mut builder = GitignoreBuilder::new(dir);
+    let mut found = false;
+    for name in IGNORE_FILES {
+        let path = dir.join(name);
+        if path.is_file() {
+            // a malformed line is skipped, the other rules still apply
+            let _ = builder.add(path);
+            found = true;
+        }
+    }
+    if !found {
+        return None;
+    }
+    builder.build().ok()
 }
 
 impl FileManager {
-    pub fn update<F>(&self, updater: F) -> Result<(), CoreError>
-    where
-        F: Fn(String) -> String,
-    {
+    /// Name and content of every file.
+    pub fn read(&self) -> Result<Vec<(String, String)>, CoreError> {
+        let mut contents = vec![];
         for (file_name, file_path) in &self.files {
             let mut file = fs::File::open(file_path)?;
             let mut content = String::new();
             file.read_to_string(&mut content)?;
-            let result = updater(content);
-            let mut file = fs::File::create(file_name)?; // Open the file in write mode (truncate the file)
-            file.write_all(result.as_bytes())?; // Write the new content
-            println!("{:?}: updated", file_name)
+            contents.push((file_name.clone(), content));
+        }
+        Ok(contents)
+    }
+
+    /// Runs `updater` on the name and content of every file in memory, without writing anything.
+    pub fn process<F>(&self, updater: F) -> Result<Vec<FileChange>, CoreError>
+    where
+        F: Fn(&str, String) -> Result<String, CoreError>,
+    {
+        let mut changes = vec![];
+        for ((file_name, content), file_path) in self.read()?.into_iter().zip(self.files.values()) {
+            let updated =
+                updater(&file_name, content.clone()).map_err(|err| CoreError::FileError {
+                    path: file_name.clone(),
+                    err: Box::new(err),
+                })?;
+            changes.push(FileChange {
+                file_name,
+                path: file_path.clone(),
+                original: content,
+                updated,
+            });
+        }
+        Ok(changes)
+    }
+
+    /// Writes the files `updater` changes, once every file has been processed, keeping their
+    /// original content as `backup` says. Returns the changed files.
+    pub fn update<F>(
+        &self,
+        updater: F,
+        backup: Option<BackupMode>,
+        journal_path: &Path,
+    ) -> Result<Vec<FileChange>, CoreError>
+    where
+        F: Fn(&str, String) -> Result<String, CoreError>,
+    {
+        let changes: Vec<_> = self
+            .process(updater)?
+            .into_iter()
+            .filter(|change| change.is_changed())
+            .collect();
+        Self::write_changes(&changes, backup, journal_path)?;
+        Ok(changes)
+    }
+
+    /// Writes the updated content of `changes`, possibly coming from several file managers,
+    /// after keeping their original content as `backup` says. The journal records all of them.
+    pub fn write_changes(
+        changes: &[FileChange],
+        backup: Option<BackupMode>,
+        journal_path: &Path,
+    ) -> Result<(), CoreError> {
+        let changes: Vec<_> = changes
+            .iter()
+            .filter(|change| change.is_changed())
+            .collect();
+        match backup {
+            Some(BackupMode::Orig) => {
+                for change in &changes {
+                    write_atomic(&orig_path(&change.path), &change.original)?;
+                }
+            }
+            Some(BackupMode::Journal) if !changes.is_empty() => {
+                let journal = UndoJournal {
+                    files: changes
+                        .iter()
+                        .map(|change| JournalEntry {
+                            path: change.path.clone(),
+                            original: change.original.clone(),
+                            updated: change.updated.clone(),
+                        })
+                        .collect(),
+                };
+                let journal = serde_json::to_string_pretty(&journal).unwrap_or_default();
+                write_atomic(journal_path, &journal)?;
+            }
+            _ => {}
+        }
+        for change in changes {
+            write_atomic(&change.path, &change.updated)?;
         }
         Ok(())
     }
 
+    /// Restores the files recorded in the journal at `journal_path`, then removes it.
+    ///
+    /// Files modified since the update are left alone, their paths are returned.
+    pub fn undo(journal_path: &Path) -> Result<UndoOutcome, CoreError> {
+        let journal: UndoJournal = serde_json::from_str(&fs::read_to_string(journal_path)?)
+            .map_err(|err| CoreError::InvalidJournal {
+                path: journal_path.display().to_string(),
+                err: err.to_string(),
+            })?;
+        let mut outcome = UndoOutcome::default();
+        for entry in journal.files {
+            if fs::read_to_string(&entry.path)? != entry.updated {
+                outcome.skipped.push(entry.path);
+                continue;
+            }
+            write_atomic(&entry.path, &entry.original)?;
+            outcome.restored.push(entry.path);
+        }
+        fs::remove_file(journal_path)?;
+        Ok(outcome)
+    }
+
     pub fn print(&self) {
         eprintln!("files = {:#?}", self.files);
     }
 }
+
+/// `<file>.orig` next to `path`.
+fn orig_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".orig");
+    path.with_file_name(name)
+}
+
+/// Writes `content` to a temporary file next to `path`, then renames it to `path`, so that
+/// `path` is never left half written. The permissions of an existing file are kept.
+pub fn write_atomic(path: &Path, content: &str) -> Result<(), CoreError> {
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(path.file_name().unwrap_or_default());
+    temp_name.push(".orn-tmp");
+    let temp_path = path.with_file_name(temp_name);
+    let result = (|| {
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(content.as_bytes())?;
+        file.sync_all()?;
+        if let Ok(metadata) = fs::metadata(path) {
+            fs::set_permissions(&temp_path, metadata.permissions())?;
+        }
+        fs::rename(&temp_path, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    Ok(result?)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use crate::file_manager::{write_atomic, BackupMode, FileChange, FileManager, UndoOutcome};
+
+    #[test]
+    fn test_load_skips_ignored_files() {
+        // a checkout in a directory of `SKIPPED_DIRS` is still searched
+        let top_dir = std::env::temp_dir().join("orn_test_load_skips_ignored_files");
+        let dir = top_dir.join("build");
+        let _ = fs::remove_dir_all(&top_dir);
+        for sub_dir in ["sources", "build/deps/sources", "vendored", "generated"] {
+            fs::create_dir_all(dir.join(sub_dir)).unwrap();
+        }
+        fs::create_dir_all(dir.join(".git")).unwrap();
+        for file in [
+            "sources/a.move",
+            "sources/b.move",
+            "build/deps/sources/c.move",
+            "vendored/d.move",
+            "generated/e.move",
+            "generated/keep.move",
+        ] {
+            fs::write(dir.join(file), "").unwrap();
+        }
+        fs::write(dir.join(".gitignore"), "vendored/\n").unwrap();
+        fs::write(dir.join("generated/.ornignore"), "*.move\n!keep.move\n").unwrap();
+
+        let patterns = vec![format!("{}/**/*.move", dir.display())];
+        let excludes = vec![format!("{}/**/b.move", dir.display())];
+        let file_manager = FileManager::load_with_excludes(&patterns, &excludes).unwrap();
+        let files: Vec<_> = file_manager
+            .files()
+            .map(|path| path.strip_prefix(&dir).unwrap().display().to_string())
+            .collect();
+        assert_eq!(files, vec!["generated/keep.move", "sources/a.move"]);
+        fs::remove_dir_all(&top_dir).unwrap();
+    }
+
+    #[test]
+    fn test_unified_diff() {
+        let change = FileChange {
+            file_name: "sources/fri_layer.move".to_string(),
+            path: PathBuf::from("sources/fri_layer.move"),
+            original: "module a::b {\n    fun f(): u64 { MAX() }\n}\n".to_string(),
+            updated: "module a::b {\n    fun f(): u64 { MAX }\n}\n".to_string(),
+        };
+        assert!(change.is_changed());
+        assert_eq!(
+            change.unified_diff(),
+            "--- a/sources/fri_layer.move\n+++ b/sources/fri_layer.move\n@@ -1,3 +1,3 @@\n module a::b {\n-    fun f(): u64 { MAX() }\n+    fun f(): u64 { MAX }\n }\n"
+        );
+    }
+
+    #[test]
+    fn test_write_atomic_keeps_permissions() {
+        let path = std::env::temp_dir().join("orn_test_write_atomic.move");
+        fs::write(&path, "old").unwrap();
+        let mut permissions = fs::metadata(&path).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&path, permissions).unwrap();
+
+        write_atomic(&path, "new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert!(fs::metadata(&path).unwrap().permissions().readonly());
+        let mut permissions = fs::metadata(&path).unwrap().permissions();
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(false);
+        fs::set_permissions(&path, permissions).unwrap();
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_update_and_undo() {
+        let dir = std::env::temp_dir().join("orn_test_update_and_undo");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let (a, b) = (dir.join("a.move"), dir.join("b.move"));
+        fs::write(&a, "MAX()").unwrap();
+        fs::write(&b, "unchanged").unwrap();
+        let journal = dir.join("journal.json");
+        let file_manager = FileManager::load(&vec![format!("{}/*.move", dir.display())]).unwrap();
+        let updater = |_: &str, content: String| Ok(content.replace("MAX()", "MAX"));
+
+        file_manager
+            .update(updater, Some(BackupMode::Orig), &journal)
+            .unwrap();
+        assert_eq!(fs::read_to_string(&a).unwrap(), "MAX");
+        assert_eq!(
+            fs::read_to_string(dir.join("a.move.orig")).unwrap(),
+            "MAX()"
+        );
+        assert!(!dir.join("b.move.orig").exists());
+
+        fs::write(&a, "MAX()").unwrap();
+        file_manager
+            .update(updater, Some(BackupMode::Journal), &journal)
+            .unwrap();
+        assert_eq!(fs::read_to_string(&a).unwrap(), "MAX");
+        let outcome = FileManager::undo(&journal).unwrap();
+        assert_eq!(outcome.restored, vec![a.clone()]);
+        assert!(outcome.skipped.is_empty());
+        assert_eq!(fs::read_to_string(&a).unwrap(), "MAX()");
+        assert!(!journal.exists());
+
+        file_manager
+            .update(updater, Some(BackupMode::Journal), &journal)
+            .unwrap();
+        fs::write(&a, "edited by hand").unwrap();
+        assert_eq!(
+            FileManager::undo(&journal).unwrap(),
+            UndoOutcome {
+                restored: vec![],
+                skipped: vec![a.clone()],
+            }
+        );
+        assert_eq!(fs::read_to_string(&a).unwrap(), "edited by hand");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_journal_of_several_packages() {
+        let dir = std::env::temp_dir().join("orn_test_journal_of_several_packages");
+        let _ = fs::remove_dir_all(&dir);
+        let mut changes = vec![];
+        let updater = |_: &str, content: String| Ok(content.replace("MAX()", "MAX"));
+        for package in ["a", "b"] {
+            fs::create_dir_all(dir.join(package)).unwrap();
+            fs::write(dir.join(package).join("m.move"), "MAX()").unwrap();
+            let pattern = format!("{}/{}/*.move", dir.display(), package);
+            let file_manager = FileManager::load(&vec![pattern]).unwrap();
+            changes.extend(file_manager.process(updater).unwrap());
+        }
+        let journal = dir.join("journal.json");
+        FileManager::write_changes(&changes, Some(BackupMode::Journal), &journal).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("b/m.move")).unwrap(), "MAX");
+
+        assert_eq!(FileManager::undo(&journal).unwrap().restored.len(), 2);
+        for package in ["a", "b"] {
+            let content = fs::read_to_string(dir.join(package).join("m.move")).unwrap();
+            assert_eq!(content, "MAX()");
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
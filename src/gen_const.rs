@@ -1,248 +1,697 @@
 use std::collections::{HashMap, HashSet};
+use std::ops::Range;
 
-use const_format::concatcp;
-use fancy_regex::{Captures, Regex};
+use crate::const_values::{constant_name, lookup_constant, ConstantValue};
+use crate::core_error::CoreError;
+use crate::diagnostics::Findings;
+use crate::extract::is_constant_name;
+use crate::file_manager::FileChange;
+use crate::move_parser::{FunctionDecl, Module, SourceFile, TokenKind, UseDecl};
 
-use crate::const_values::ConstantValue;
+const BLOCK_BEGIN_MARKER: &str = "// This line is used for generating constants DO NOT REMOVE!";
+const BLOCK_END_MARKER: &str = "// End of generating constants!";
+const DEFAULT_INDENT: &str = "    ";
 
-const SNAKE_CASE_PATTERN: &str = r"([A-Z][A-Z0-9]*[_[A-Z0-9]+]+)";
-const ANY_CHAR_PATTERN: &str = r"([^}]*)";
-const IMPORT_STATEMENT_PATTERN: &str = r"\s*use\s+.*::([^;]+);";
-const CONST_BLOCK_BEGIN: &str =
-    "    // This line is used for generating constants DO NOT REMOVE!\n";
-const CONST_BLOCK_END: &str = "    // End of generating constants!\n\n";
-const IMPORT_BLOCK: &str = r"((\n\s*use\s+[^\n]+;)|(\s+\/\/.*))+\n";
+/// Declares the constants `consts`, a map from the names to their keys in `table`.
+fn create_const_block(
+    consts: &HashMap<String, String>,
+    table: &HashMap<String, ConstantValue>,
+    indent: &str,
+) -> Result<String, CoreError> {
+    let mut consts: Vec<_> = consts.iter().collect();
+    consts.sort();
 
-fn warn_const_unused(consts: &HashSet<String>, table: &HashMap<String, ConstantValue>) {
-    let table_keys: Vec<_> = table.keys().collect();
-    let consts_unused: Vec<_> = table_keys
-        .into_iter()
-        .filter(|item| !consts.contains(*item))
-        .collect();
-    for e in consts_unused {
-        println!("Unused: {}", e);
+    let mut result = format!("{}{}\n", indent, BLOCK_BEGIN_MARKER);
+    for (c, key) in consts {
+        let info = table
+            .get(key)
+            .ok_or_else(|| CoreError::UnknownConstant(key.to_string()))?;
+        if let Some(message) = &info.error_message {
+            for line in message.lines() {
+                result += format!("{}/// {}\n", indent, line).as_str();
+            }
+            result += format!("{}#[error]\n", indent).as_str();
+        } else if let Some(comment) = info.comment.clone() {
+            result += format!("{}// {}\n", indent, comment).as_str();
+        }
+        result += format!("{}const {}: {} = {};\n", indent, c, info.r#type, info.value).as_str();
     }
+    result += format!("{}{}\n\n", indent, BLOCK_END_MARKER).as_str();
+    Ok(result)
 }
 
-fn create_const_block(consts: &HashSet<String>, table: &HashMap<String, ConstantValue>) -> String {
-    if consts.is_empty() {
-        return "".to_string();
+/// Indentation of the first line of the body of `module`.
+fn body_indent<'a>(file: &SourceFile<'a>, module: &Module) -> &'a str {
+    let after_brace = &file.tokens[module.open_brace + 1];
+    if after_brace.kind != TokenKind::Whitespace || module.open_brace + 2 == module.close_brace {
+        return DEFAULT_INDENT;
+    }
+    match after_brace.text.rsplit_once('\n') {
+        Some((_, indent)) => indent,
+        None => DEFAULT_INDENT,
     }
+}
 
-    let mut consts: Vec<_> = consts.iter().collect();
-    consts.sort();
+/// Replacements of byte ranges of a source, applied all at once.
+#[derive(Default)]
+struct Edits {
+    edits: Vec<(Range<usize>, String)>,
+}
+
+impl Edits {
+    fn replace(&mut self, range: Range<usize>, text: &str) {
+        self.edits.push((range, text.to_string()));
+    }
+
+    fn remove(&mut self, range: Range<usize>) {
+        self.replace(range, "");
+    }
+
+    fn insert(&mut self, position: usize, text: &str) {
+        self.replace(position..position, text);
+    }
+
+    /// `position`, or the end of the edit it falls strictly inside of.
+    fn outside_edits(&self, position: usize) -> usize {
+        self.edits
+            .iter()
+            .find(|(range, _)| range.start < position && position < range.end)
+            .map_or(position, |(range, _)| range.end)
+    }
 
-    let mut result = CONST_BLOCK_BEGIN.to_string();
-    for c in consts {
-        let info = table.get(c).unwrap();
-        if let Some(comment) = info.comment.clone() {
-            result += format!("    // {}\n", comment).as_str();
+    /// Applies the edits, failing if two of them overlap.
+    fn apply(mut self, source: &str) -> Result<String, CoreError> {
+        self.edits
+            .sort_by_key(|(range, _)| (range.start, range.end));
+        let mut result = String::with_capacity(source.len());
+        let mut position = 0;
+        for (range, text) in self.edits {
+            if range.start < position {
+                return Err(CoreError::MalformedMoveFile(format!(
+                    "conflicting changes at byte {}",
+                    range.start
+                )));
+            }
+            result += &source[position..range.start];
+            result += &text;
+            position = range.end;
         }
-        result += format!("    const {}: {} = {};\n", c, info.r#type, info.value).as_str();
+        result += &source[position..];
+        Ok(result)
     }
-    result += CONST_BLOCK_END;
-    result
 }
 
-fn remove_import(file_content: &str, table: &HashMap<String, ConstantValue>) -> String {
-    Regex::new(IMPORT_STATEMENT_PATTERN)
-        .unwrap()
-        .replace_all(file_content, |cap: &Captures| {
-            let mut new_statement = cap[1].trim().to_string();
-            if Regex::new(SNAKE_CASE_PATTERN)
-                .unwrap()
-                .is_match(&new_statement)
-                .unwrap()
-            {
-                if new_statement.starts_with('{') {
-                    // Case 1: CONST,
-                    new_statement = Regex::new(&format!(r"{}\s*,\s*", SNAKE_CASE_PATTERN))
-                        .unwrap()
-                        .replace_all(&new_statement, |import_caps: &Captures| {
-                            let const_name = import_caps[1].to_string();
-                            if table.contains_key(&const_name) {
-                                "".to_string()
-                            } else {
-                                import_caps[0].to_string()
-                            }
-                        })
-                        .to_string();
-                    // Case 2: , CONST and CONST
-                    new_statement = Regex::new(&format!(r",?\s*{}\s*", SNAKE_CASE_PATTERN))
-                        .unwrap()
-                        .replace_all(&new_statement, |import_caps: &Captures| {
-                            let const_name = import_caps[1].to_string();
-                            if table.contains_key(&const_name) {
-                                "".to_string()
-                            } else {
-                                import_caps[0].to_string()
-                            }
-                        })
-                        .to_string();
-                } else {
-                    new_statement = "".to_string();
-                };
-                if new_statement.ends_with("{}") {
-                    new_statement = "".to_string();
-                }
-                if new_statement.is_empty() {
-                    "".to_string()
-                } else {
-                    cap[0]
-                        .replace(cap[1].to_string().as_str(), new_statement.as_ref())
-                        .to_string()
-                }
-            } else {
-                cap[0].to_string()
-            }
-        })
-        .to_string()
+/// Byte range of the tokens `first..=last` extended to the whitespace before them.
+fn with_leading_whitespace(file: &SourceFile, first: usize, last: usize) -> Range<usize> {
+    let mut range = file.span(first, last);
+    if first > 0 && file.tokens[first - 1].kind == TokenKind::Whitespace {
+        range.start = file.tokens[first - 1].start;
+    }
+    range
 }
 
-pub fn get_import_regex(table: &HashMap<String, ConstantValue>) -> String {
-    let any_character = r"[a-zA-Z0-9_:,{}()\s]*";
-    format!(
-        r"    use\s+{}({}){};\n",
-        any_character,
-        get_const_regex(table),
-        any_character,
-    )
-    .to_string()
+/// Tokens of the constants block generated by a previous run, from the begin to the end marker.
+pub(crate) fn generated_block(file: &SourceFile, module: &Module) -> Option<Range<usize>> {
+    let is_marker = |index: &usize, marker: &str| {
+        let token = &file.tokens[*index];
+        token.kind == TokenKind::LineComment && token.text.trim_end() == marker
+    };
+    let mut tokens = module.open_brace + 1..module.close_brace;
+    let begin = tokens.find(|i| is_marker(i, BLOCK_BEGIN_MARKER))?;
+    let end = tokens.find(|i| is_marker(i, BLOCK_END_MARKER))?;
+    Some(begin..end + 1)
 }
 
-pub fn get_const_funcs_regex(table: &HashMap<String, ConstantValue>) -> String {
-    format!(
-        "{}({}){}{}{}",
-        r"\s*public fun ",
-        get_const_regex(table),
-        r"\(\)\s*:\s*(\w+)\s*\{\s*",
-        ANY_CHAR_PATTERN,
-        r"*\}"
-    )
-    .to_string()
+/// Byte range of a generated block: whole lines, followed by at most one blank line.
+fn generated_block_span(file: &SourceFile, tokens: &Range<usize>) -> Range<usize> {
+    let source = file.source;
+    let begin = file.tokens[tokens.start].start;
+    let start = source[..begin].rfind('\n').map_or(0, |i| i + 1);
+    let mut end = file.tokens[tokens.end - 1].end();
+    for _ in 0..2 {
+        if source[end..].starts_with('\n') {
+            end += 1;
+        }
+    }
+    start..end
 }
 
-pub fn get_const_regex(table: &HashMap<String, ConstantValue>) -> String {
-    let mut result = table.iter().map(|(k, _)| k).collect::<Vec<_>>();
-    result.sort();
-    result.reverse();
-    let result = result
-        .into_iter()
-        .fold("".to_string(), |acc, k| format!("{}({})|", acc, k));
-    result[0..result.len() - 1].to_string()
+/// Whether `function` is a getter such as `public fun NAME(): u256 { ... }` of a constant
+/// `table` defines for `module`.
+fn is_const_getter(
+    function: &FunctionDecl,
+    module: &Module,
+    table: &HashMap<String, ConstantValue>,
+) -> bool {
+    function.is_public
+        && !function.has_params
+        && function.body.is_some()
+        && lookup_constant(table, &module.name, &function.name).is_some()
 }
 
-pub fn gen_consts(file_content: &str, table: &HashMap<String, ConstantValue>) -> String {
-    // remove constant function declaration
-    let mut declared_funcs = vec![];
-    let mut result = Regex::new(&get_const_funcs_regex(table))
-        .unwrap()
-        .replace_all(file_content, |cap: &Captures| {
-            declared_funcs.push(cap[1].to_string());
-            ""
-        })
-        .to_string();
-
-    // remove constants block if it was generated before
-    result = Regex::new(concatcp!(
-        CONST_BLOCK_BEGIN,
-        ANY_CHAR_PATTERN,
-        CONST_BLOCK_END
-    ))
-    .unwrap()
-    .replace_all(&result, |_: &Captures| {
-        format!("{}{}", CONST_BLOCK_BEGIN, CONST_BLOCK_END)
-    })
-    .to_string();
-
-    // remove '()' if it's a constant function call
-    let mut consts = HashSet::<String>::new();
-    let const_regex_func_calls = format!(r"(.*?[^\w]*({}))(\(\))?", get_const_regex(table));
-    let comment_regex = Regex::new(r"^\s+\/\/").unwrap();
-    result = Regex::new(&const_regex_func_calls)
-        .unwrap()
-        .replace_all(&result, |caps: &Captures| {
-            let whole = caps[0].to_string();
-            if comment_regex.is_match(&whole).unwrap() {
-                return whole;
+/// Removes the constants `table` defines for `module` imported by `use_decl`, returns whether
+/// the whole declaration is removed.
+fn remove_imports(
+    file: &SourceFile,
+    module: &Module,
+    use_decl: &UseDecl,
+    table: &HashMap<String, ConstantValue>,
+    edits: &mut Edits,
+) -> bool {
+    let kept: Vec<_> = use_decl
+        .members
+        .iter()
+        .map(|member| lookup_constant(table, &module.name, &member.name).is_none())
+        .collect();
+    if kept.iter().all(|kept| *kept) {
+        return false;
+    }
+    if kept.iter().all(|kept| !*kept) {
+        edits.remove(with_leading_whitespace(file, use_decl.start, use_decl.end));
+        return true;
+    }
+    let Some((_, close)) = use_decl.group else {
+        return false;
+    };
+    let members = &use_decl.members;
+    for (i, member) in members.iter().enumerate() {
+        if kept[i] {
+            continue;
+        }
+        let start = file.tokens[member.first].start;
+        match kept[i..].iter().position(|kept| *kept) {
+            // remove up to the next member, separator included
+            Some(_) => edits.remove(start..file.tokens[members[i + 1].first].start),
+            // remove from the end of the last kept member up to the closing brace
+            None => {
+                let last_kept = kept[..i].iter().rposition(|kept| *kept).unwrap_or(0);
+                edits.remove(file.tokens[members[last_kept].last].end()..file.tokens[close].start);
+                break;
             }
-            let wrapped_name = caps[1].to_string();
-            let name = caps[2].to_string();
-
-            if table.contains_key(&name) {
-                consts.insert(name.clone());
-                wrapped_name
-            } else {
-                whole
+        }
+    }
+    false
+}
+
+/// Position at the end of the `use` declarations at the top of `module`, and the trailing line
+/// comments directly following them, or `None` if no declaration is left.
+fn end_of_imports(file: &SourceFile, module: &Module, removed: &[bool]) -> Option<usize> {
+    let first = removed.iter().position(|removed| !*removed)?;
+    let mut last = module.uses[first].end;
+    for (i, use_decl) in module.uses.iter().enumerate().skip(first + 1) {
+        if file.next_significant(module.uses[i - 1].end) != Some(use_decl.start) {
+            break;
+        }
+        if !removed[i] {
+            last = use_decl.end;
+        }
+    }
+    // keep the comments that directly follow the imports, e.g. commented out imports
+    let mut index = last + 1;
+    while index + 1 < module.close_brace {
+        let whitespace = &file.tokens[index];
+        let comment = &file.tokens[index + 1];
+        if whitespace.kind != TokenKind::Whitespace
+            || whitespace.text.matches('\n').count() > 1
+            || comment.kind != TokenKind::LineComment
+        {
+            break;
+        }
+        last = index + 1;
+        index += 2;
+    }
+    Some(file.tokens[last].end())
+}
+
+/// Rewrites the constant getters, their calls and imports in `module`, and updates its
+/// constants block to declare exactly the constants it references.
+fn update_module(
+    file: &SourceFile,
+    module: &Module,
+    table: &HashMap<String, ConstantValue>,
+    edits: &mut Edits,
+    findings: &mut Findings,
+) -> Result<(), CoreError> {
+    let mut consts = HashMap::<String, String>::new();
+    let block = generated_block(file, module);
+    let in_block = |token: usize| block.as_ref().is_some_and(|block| block.contains(&token));
+
+    // remove constant function declaration
+    let mut removed_functions = vec![];
+    let mut declared = HashSet::new();
+    for function in &module.functions {
+        if is_const_getter(function, module, table) {
+            edits.remove(with_leading_whitespace(file, function.start, function.end));
+            removed_functions.push(function.start..function.end + 1);
+        } else {
+            declared.insert(function.name.as_str());
+        }
+    }
+    // constants declared by hand are left alone
+    for constant in &module.consts {
+        if !in_block(constant.name_token) {
+            declared.insert(constant.name.as_str());
+            if lookup_constant(table, &module.name, &constant.name).is_some() {
+                findings
+                    .shadowed
+                    .push((module.name.clone(), constant.name.clone()));
             }
-        })
-        .to_string();
+        }
+    }
 
-    result = remove_import(&result, table);
+    // constants imported under another name are referred to by their own name again
+    let aliases: HashMap<_, _> = module
+        .uses
+        .iter()
+        .flat_map(|use_decl| &use_decl.members)
+        .filter_map(|member| Some((member.alias.as_deref()?, member.name.as_str())))
+        .filter(|(_, name)| lookup_constant(table, &module.name, name).is_some())
+        .collect();
 
-    // insert constants block
-    if consts.is_empty() {
-        return result;
+    // remove '()' and the module path if it's a constant function call
+    for reference in &module.references {
+        let text = file.text(reference.token);
+        let alias = aliases
+            .get(text)
+            .filter(|_| reference.qualifier.is_none() && !declared.contains(text));
+        let name = alias.copied().unwrap_or(text);
+        if declared.contains(name)
+            || in_block(reference.token)
+            || removed_functions
+                .iter()
+                .any(|function| function.contains(&reference.token))
+        {
+            continue;
+        }
+        let Some((key, _)) = lookup_constant(table, &module.name, name) else {
+            // single letters are usually type parameters
+            if name.len() > 1 && is_constant_name(name) {
+                findings
+                    .missing
+                    .push((module.name.clone(), name.to_string()));
+            }
+            continue;
+        };
+        consts.insert(name.to_string(), key.clone());
+        let tokens = reference.tokens();
+        if tokens.len() > 1 || alias.is_some() {
+            edits.replace(file.span(tokens.start, tokens.end - 1), name);
+        }
     }
 
-    let const_block = create_const_block(&consts, table);
-    warn_const_unused(&consts, table);
-    // replace old constants block with new block
-    if result.contains(format!("{}{}", CONST_BLOCK_BEGIN, CONST_BLOCK_END).as_str()) {
-        return result.replace(
-            format!("{}{}", CONST_BLOCK_BEGIN, CONST_BLOCK_END).as_str(),
-            &const_block,
-        );
+    let removed_uses: Vec<_> = module
+        .uses
+        .iter()
+        .map(|use_decl| remove_imports(file, module, use_decl, table, edits))
+        .collect();
+
+    // update the constants block of the module
+    let indent = body_indent(file, module);
+    if let Some(block) = &block {
+        let span = generated_block_span(file, block);
+        edits.replace(span, &create_const_block(&consts, table, indent)?);
+    } else if !consts.is_empty() {
+        let const_block = create_const_block(&consts, table, indent)?;
+        match end_of_imports(file, module, &removed_uses) {
+            Some(position) => edits.insert(position, &format!("\n\n{}", const_block)),
+            None => {
+                // insert into the beginning of the module, on a new line for one-line modules
+                let open = file.tokens[module.open_brace].end();
+                let close = file.tokens[module.close_brace].start;
+                match file.source[open..close].find('\n') {
+                    Some(line_end) => {
+                        // the removed imports may take the line break after the brace along
+                        let line = open + line_end + 1;
+                        match edits.outside_edits(line) {
+                            position if position == line => edits.insert(line, &const_block),
+                            position => edits.insert(position, &format!("\n{}", const_block)),
+                        }
+                    }
+                    None => edits.insert(open, &format!("\n{}", const_block)),
+                }
+            }
+        }
     }
+    let mut keys: Vec<_> = consts.into_values().collect();
+    keys.sort();
+    findings
+        .uses
+        .extend(keys.iter().map(|key| (module.name.clone(), key.clone())));
+    findings.used.extend(keys);
+    Ok(())
+}
 
-    let mut contain_import_block = false;
-    result = Regex::new(IMPORT_BLOCK)
-        .unwrap()
-        .replace(&result, |caps: &Captures| {
-            contain_import_block = true;
-            let whole = caps[0].to_string();
-            whole + "\n" + &const_block + "\n"
-        })
-        .to_string();
+pub fn gen_consts(
+    file_content: &str,
+    table: &HashMap<String, ConstantValue>,
+) -> Result<String, CoreError> {
+    generate(file_content, table).map(|(content, _)| content)
+}
 
-    if contain_import_block {
-        return result;
+/// Same as [`gen_consts`], also returning what was noticed in the file.
+pub fn generate(
+    file_content: &str,
+    table: &HashMap<String, ConstantValue>,
+) -> Result<(String, Findings), CoreError> {
+    let mut findings = Findings::default();
+    if table.is_empty() {
+        return Ok((file_content.to_string(), findings));
+    }
+    let file = SourceFile::parse(file_content)?;
+    if file.modules.is_empty() {
+        return Ok((file_content.to_string(), findings));
     }
 
-    // insert into the beginning of the module
-    let mut first_left_cb = result.find('{').unwrap();
-    while result.as_bytes()[first_left_cb] != u8::try_from('\n').unwrap() {
-        first_left_cb += 1;
+    let mut edits = Edits::default();
+    for module in &file.modules {
+        update_module(&file, module, table, &mut edits, &mut findings)?;
     }
-    result.insert_str(first_left_cb + 1, &const_block);
+    findings.missing.sort();
+    findings.missing.dedup();
+    Ok((edits.apply(file_content)?, findings))
+}
 
-    result
+/// Constants of `table` appearing on the lines `gen_consts` changed in a file, sorted by name.
+pub fn stale_constants(change: &FileChange, table: &HashMap<String, ConstantValue>) -> Vec<String> {
+    let names: HashSet<_> = table.keys().map(|key| constant_name(key)).collect();
+    let changed_lines = change.changed_lines();
+    let mut consts = changed_lines
+        .iter()
+        .flat_map(|line| line.split(|c: char| !c.is_ascii_alphanumeric() && c != '_'))
+        .filter(|word| names.contains(word))
+        .map(|word| word.to_string())
+        .collect::<Vec<_>>();
+    consts.sort();
+    consts.dedup();
+    consts
 }
 
 #[cfg(test)]
 mod test {
-    use crate::const_values::get_constant_values;
-    use crate::gen_const::gen_consts;
+    use std::collections::HashMap;
+
+    use crate::const_values::{get_constant_values, table_from_str};
+    use crate::core_error::CoreError;
+    use crate::file_manager::FileChange;
+    use crate::gen_const::{gen_consts, generate, stale_constants, Edits};
 
     #[test]
     fn test_gen_consts_sample1() {
         let file_content = include_str!("./test_files/sample1_input.move");
         let refined_content = include_str!("./test_files/sample1_expect.move");
-        let output = gen_consts(file_content, &get_constant_values());
+        let output = gen_consts(file_content, &get_constant_values().unwrap()).unwrap();
         assert_eq!(output, refined_content, "failed");
     }
     #[test]
     fn test_gen_consts_sample2() {
         let file_content = include_str!("./test_files/sample2_input.move");
         let refined_content = include_str!("./test_files/sample2_expect.move");
-        let output = gen_consts(file_content, &get_constant_values());
+        let output = gen_consts(file_content, &get_constant_values().unwrap()).unwrap();
         assert_eq!(output, refined_content, "failed");
     }
     #[test]
     fn test_gen_consts_sample3() {
         let file_content = include_str!("./test_files/sample3_input.move");
         let refined_content = include_str!("./test_files/sample3_expect.move");
-        let output = gen_consts(file_content, &get_constant_values());
+        let output = gen_consts(file_content, &get_constant_values().unwrap()).unwrap();
         assert_eq!(output, refined_content, "failed");
     }
+    #[test]
+    fn test_gen_consts_multiple_modules() {
+        let table = get_constant_values().unwrap();
+        let file_content = include_str!("./test_files/sample4_input.move");
+        let refined_content = include_str!("./test_files/sample4_expect.move");
+        let output = gen_consts(file_content, &table).unwrap();
+        assert_eq!(output, refined_content, "failed");
+        assert_eq!(gen_consts(&output, &table).unwrap(), output);
+    }
+
+    #[test]
+    fn test_gen_consts_scoped_constants() {
+        let table = table_from_str(
+            r#"
+EINVALID_INPUT = { type = "u64", value = "1" }
+
+["verifier_addr::fri_layer"]
+EINVALID_INPUT = { type = "u64", value = "7" }
+"#,
+        )
+        .unwrap();
+        let output = gen_consts(
+            "module verifier_addr::fri_layer {\n    fun f() { abort EINVALID_INPUT() }\n}\n\
+             module verifier_addr::fri_transform {\n    fun f() { abort EINVALID_INPUT() }\n}\n",
+            &table,
+        )
+        .unwrap();
+        assert_eq!(
+            output,
+            "module verifier_addr::fri_layer {\n\
+             \x20   // This line is used for generating constants DO NOT REMOVE!\n\
+             \x20   const EINVALID_INPUT: u64 = 0x7;\n\
+             \x20   // End of generating constants!\n\n\
+             \x20   fun f() { abort EINVALID_INPUT }\n}\n\
+             module verifier_addr::fri_transform {\n\
+             \x20   // This line is used for generating constants DO NOT REMOVE!\n\
+             \x20   const EINVALID_INPUT: u64 = 0x1;\n\
+             \x20   // End of generating constants!\n\n\
+             \x20   fun f() { abort EINVALID_INPUT }\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_gen_consts_one_line_module() {
+        let table = get_constant_values().unwrap();
+        let output = gen_consts(
+            "module verifier_addr::a { fun f(): u64 { MAX_N_QUERIES() } }\n\
+             module verifier_addr::b {\n    fun g(): u64 { 1 }\n}\n",
+            &table,
+        )
+        .unwrap();
+        assert_eq!(
+            output,
+            "module verifier_addr::a {\n\
+             \x20   // This line is used for generating constants DO NOT REMOVE!\n\
+             \x20   // 48\n\
+             \x20   const MAX_N_QUERIES: u64 = 0x30;\n\
+             \x20   // End of generating constants!\n\n\
+             \x20fun f(): u64 { MAX_N_QUERIES } }\n\
+             module verifier_addr::b {\n    fun g(): u64 { 1 }\n}\n"
+        );
+        assert_eq!(gen_consts(&output, &table).unwrap(), output);
+    }
+
+    #[test]
+    fn test_gen_consts_removed_imports() {
+        let table = get_constant_values().unwrap();
+        let output = gen_consts(
+            "module verifier_addr::a {\n\
+             \x20   use verifier_addr::fri_layer::MAX_N_QUERIES;\n\n\
+             \x20   fun f(): u64 { MAX_N_QUERIES() }\n}\n",
+            &table,
+        )
+        .unwrap();
+        assert_eq!(
+            output,
+            "module verifier_addr::a {\n\
+             \x20   // This line is used for generating constants DO NOT REMOVE!\n\
+             \x20   // 48\n\
+             \x20   const MAX_N_QUERIES: u64 = 0x30;\n\
+             \x20   // End of generating constants!\n\n\n\n\
+             \x20   fun f(): u64 { MAX_N_QUERIES }\n}\n"
+        );
+        assert_eq!(gen_consts(&output, &table).unwrap(), output);
+    }
+
+    #[test]
+    fn test_gen_consts_aliased_imports() {
+        let table = get_constant_values().unwrap();
+        let output = gen_consts(
+            "module verifier_addr::a {\n\
+             \x20   use verifier_addr::fri_layer::{MAX_N_QUERIES as Q, verify};\n\n\
+             \x20   fun f(): u64 { verify(); Q() + Q }\n}\n",
+            &table,
+        )
+        .unwrap();
+        assert_eq!(
+            output,
+            "module verifier_addr::a {\n\
+             \x20   use verifier_addr::fri_layer::{verify};\n\n\
+             \x20   // This line is used for generating constants DO NOT REMOVE!\n\
+             \x20   // 48\n\
+             \x20   const MAX_N_QUERIES: u64 = 0x30;\n\
+             \x20   // End of generating constants!\n\n\n\n\
+             \x20   fun f(): u64 { verify(); MAX_N_QUERIES + MAX_N_QUERIES }\n}\n"
+        );
+        assert_eq!(gen_consts(&output, &table).unwrap(), output);
+    }
+
+    #[test]
+    fn test_conflicting_edits() {
+        let mut edits = Edits::default();
+        edits.remove(2..6);
+        edits.insert(6, "x");
+        assert_eq!(edits.apply("abcdefgh").unwrap(), "abxgh");
+        let mut edits = Edits::default();
+        edits.remove(2..6);
+        edits.insert(4, "x");
+        assert!(matches!(
+            edits.apply("abcdefgh"),
+            Err(CoreError::MalformedMoveFile(_))
+        ));
+    }
+
+    #[test]
+    fn test_gen_consts_montgomery_form() {
+        let table = table_from_str(
+            r#"
+K_MODULUS = { type = "u256", value = "0x800000000000011000000000000000000000000000000000000000000000001" }
+HALF = { type = "u256", value = "1 / 2", field = "K_MODULUS", montgomery = true }
+"#,
+        )
+        .unwrap();
+        let output = gen_consts(
+            "module verifier_addr::prime_field {\n    fun f(): u256 { HALF() + HALF_MONTGOMERY() }\n}\n",
+            &table,
+        )
+        .unwrap();
+        assert_eq!(
+            output,
+            "module verifier_addr::prime_field {\n\
+             \x20   // This line is used for generating constants DO NOT REMOVE!\n\
+             \x20   // 1 / 2\n\
+             \x20   const HALF: u256 = 0x400000000000008800000000000000000000000000000000000000000000001;\n\
+             \x20   // HALF in Montgomery form\n\
+             \x20   const HALF_MONTGOMERY: u256 = 0x7ffffffffffff00fffffffffffffffffffffffffffffffffffffffffffffff1;\n\
+             \x20   // End of generating constants!\n\n\
+             \x20   fun f(): u256 { HALF + HALF_MONTGOMERY }\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_gen_consts_error_codes() {
+        let table = table_from_str(
+            r#"
+[errors]
+EINVALID_INPUT = { code = 1, message = "The input is invalid" }
+EINVALID_PROOF = { code = 2, message = "The proof is invalid" }
+"#,
+        )
+        .unwrap();
+        let output = gen_consts(
+            "module 0x1::m {\n    fun f() { abort EINVALID_INPUT }\n}\n",
+            &table,
+        )
+        .unwrap();
+        assert_eq!(
+            output,
+            "module 0x1::m {\n\
+             \x20   // This line is used for generating constants DO NOT REMOVE!\n\
+             \x20   /// The input is invalid\n\
+             \x20   #[error]\n\
+             \x20   const EINVALID_INPUT: u64 = 1;\n\
+             \x20   // End of generating constants!\n\n\
+             \x20   fun f() { abort EINVALID_INPUT }\n}\n"
+        );
+        assert_eq!(gen_consts(&output, &table).unwrap(), output);
+
+        // codes are unique among those visible from a module, whatever their notation
+        let table = table_from_str(
+            r#"
+[errors]
+EINVALID_INPUT = { code = 1, message = "a" }
+
+["0x1::other".errors]
+EINVALID_PROOF = { code = 2, message = "b" }
+
+["0x1::another".errors]
+EINVALID_STEP = { code = 2, message = "c" }
+"#,
+        );
+        assert!(table.is_ok());
+        let table = table_from_str(
+            r#"
+[errors]
+EINVALID_INPUT = { code = 1, message = "a" }
+
+["0x1::other".errors]
+EINVALID_PROOF = { code = 0x1, message = "b" }
+"#,
+        );
+        assert!(matches!(table, Err(CoreError::DuplicateErrorCode { .. })));
+    }
+
+    #[test]
+    fn test_generate_findings() {
+        let table = get_constant_values().unwrap();
+        let (_, findings) =
+            generate(include_str!("./test_files/sample2_input.move"), &table).unwrap();
+        let module = "verifier_addr::stark_verifier_7".to_string();
+        assert!(findings.used.contains("MM_CONTEXT_SIZE"));
+        assert!(!findings.used.contains("PROOF_PARAMS_N_QUERIES_OFFSET"));
+        assert!(findings
+            .shadowed
+            .contains(&(module.clone(), "PROOF_PARAMS_N_QUERIES_OFFSET".to_string())));
+
+        let (_, findings) = generate(
+            "module 0x1::m {\n    fun f<T>(): u64 { MAX_N_QUERIES() + MM_UNKNOWN }\n}\n",
+            &table,
+        )
+        .unwrap();
+        assert_eq!(
+            findings.used.iter().collect::<Vec<_>>(),
+            vec!["MAX_N_QUERIES"]
+        );
+        assert_eq!(
+            findings.missing,
+            vec![("0x1::m".to_string(), "MM_UNKNOWN".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_gen_consts_malformed_file() {
+        let output = gen_consts(
+            "let x = FRI_MAX_STEP_SIZE();",
+            &get_constant_values().unwrap(),
+        );
+        assert!(matches!(output, Err(CoreError::MalformedMoveFile(_))));
+        let output = gen_consts("let x = FRI_MAX_STEP_SIZE();", &HashMap::new()).unwrap();
+        assert_eq!(output, "let x = FRI_MAX_STEP_SIZE();");
+    }
+
+    #[test]
+    fn test_gen_consts_file_without_module() {
+        let table = get_constant_values().unwrap();
+        for content in [
+            "",
+            "// FRI_MAX_STEP_SIZE\n",
+            "#[test_only]\n/* no module yet */\n",
+        ] {
+            assert_eq!(gen_consts(content, &table).unwrap(), content);
+        }
+    }
+
+    #[test]
+    fn test_stale_constants() {
+        let table = get_constant_values().unwrap();
+        let original = include_str!("./test_files/sample3_expect.move").to_string();
+        let change = FileChange {
+            file_name: "sample3.move".to_string(),
+            path: "sample3.move".into(),
+            updated: gen_consts(&original, &table).unwrap(),
+            original,
+        };
+        assert!(!change.is_changed());
+        assert!(stale_constants(&change, &table).is_empty());
+
+        let original = include_str!("./test_files/sample1_input.move").to_string();
+        let change = FileChange {
+            file_name: "sample1.move".to_string(),
+            path: "sample1.move".into(),
+            updated: gen_consts(&original, &table).unwrap(),
+            original,
+        };
+        let stale = stale_constants(&change, &table);
+        assert!(stale.contains(&"FRI_MAX_STEP_SIZE".to_string()));
+        assert!(stale.contains(&"MAX_COSET_SIZE".to_string()));
+    }
 }
@@ -1,5 +1,17 @@
+pub mod comments;
+pub mod config;
 pub mod const_values;
 pub mod core_error;
+pub mod dependency_graph;
+pub mod diagnostics;
+pub mod error_codes;
+pub mod explain;
+pub mod expression;
+pub mod extract;
 pub mod file_manager;
 pub mod gen_const;
+pub mod move_parser;
+pub mod move_type;
+pub mod number_format;
+pub mod package;
 pub mod update_notifier;
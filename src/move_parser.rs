@@ -0,0 +1,866 @@
+use std::ops::Range;
+
+use crate::core_error::CoreError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Whitespace,
+    /// `// ...`
+    LineComment,
+    /// `/// ...`
+    DocComment,
+    /// `/* ... */`
+    BlockComment,
+    Identifier,
+    Number,
+    /// `b"..."` or `x"..."`
+    ByteString,
+    /// Punctuation and operators, `::` is the only one spanning more than one character
+    Symbol,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    /// Byte offset in the source
+    pub start: usize,
+}
+
+impl Token<'_> {
+    pub fn end(&self) -> usize {
+        self.start + self.text.len()
+    }
+
+    pub fn is_trivia(&self) -> bool {
+        matches!(
+            self.kind,
+            TokenKind::Whitespace
+                | TokenKind::LineComment
+                | TokenKind::DocComment
+                | TokenKind::BlockComment
+        )
+    }
+
+    fn is(&self, kind: TokenKind, text: &str) -> bool {
+        self.kind == kind && self.text == text
+    }
+}
+
+/// Splits Move source code into tokens, whitespace and comments included, so that
+/// concatenating the tokens gives back the source byte-for-byte.
+pub fn tokenize(source: &str) -> Result<Vec<Token<'_>>, CoreError> {
+    let bytes = source.as_bytes();
+    let mut tokens = vec![];
+    let mut start = 0;
+    while start < bytes.len() {
+        let rest = &source[start..];
+        let c = rest.chars().next().unwrap_or_default();
+        let (kind, len) = if c.is_whitespace() {
+            let len = rest
+                .find(|c: char| !c.is_whitespace())
+                .unwrap_or(rest.len());
+            (TokenKind::Whitespace, len)
+        } else if rest.starts_with("//") {
+            let len = rest.find('\n').unwrap_or(rest.len());
+            let kind = if rest.starts_with("///") && !rest.starts_with("////") {
+                TokenKind::DocComment
+            } else {
+                TokenKind::LineComment
+            };
+            (kind, len)
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            let Some(end) = comment.find("*/") else {
+                return Err(malformed(source, start, "unterminated block comment"));
+            };
+            (TokenKind::BlockComment, end + 4)
+        } else if rest.starts_with("b\"") || rest.starts_with("x\"") {
+            let mut escaped = false;
+            let end = rest[2..].char_indices().find(|(_, c)| {
+                let is_end = !escaped && *c == '"';
+                escaped = !escaped && *c == '\\';
+                is_end
+            });
+            let Some((end, _)) = end else {
+                return Err(malformed(source, start, "unterminated byte string"));
+            };
+            (TokenKind::ByteString, end + 3)
+        } else if c.is_ascii_alphanumeric() || c == '_' {
+            let len = rest
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .unwrap_or(rest.len());
+            let kind = if c.is_ascii_digit() {
+                TokenKind::Number
+            } else {
+                TokenKind::Identifier
+            };
+            (kind, len)
+        } else if rest.starts_with("::") {
+            (TokenKind::Symbol, 2)
+        } else {
+            (TokenKind::Symbol, c.len_utf8())
+        };
+        tokens.push(Token {
+            kind,
+            text: &rest[..len],
+            start,
+        });
+        start += len;
+    }
+    Ok(tokens)
+}
+
+fn malformed(source: &str, offset: usize, message: &str) -> CoreError {
+    let line = source[..offset].matches('\n').count() + 1;
+    let column = offset - source[..offset].rfind('\n').map_or(0, |i| i + 1) + 1;
+    CoreError::MalformedMoveFile(format!("{} at {}:{}", message, line, column))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModuleKind {
+    Module,
+    Script,
+}
+
+/// A `module` or `script` block, token indexes refer to [`SourceFile::tokens`].
+#[derive(Debug, Clone)]
+pub struct Module {
+    pub kind: ModuleKind,
+    /// `address::name` for modules, `script` for scripts
+    pub name: String,
+    pub open_brace: usize,
+    pub close_brace: usize,
+    pub uses: Vec<UseDecl>,
+    pub functions: Vec<FunctionDecl>,
+    pub consts: Vec<ConstDecl>,
+    /// Identifiers used in the code of the module, outside `use` declarations and
+    /// declaration names
+    pub references: Vec<Reference>,
+}
+
+/// `use a::b::{c, d as e};`
+#[derive(Debug, Clone)]
+pub struct UseDecl {
+    /// `use` keyword
+    pub start: usize,
+    /// `;`
+    pub end: usize,
+    /// Imported items, in order
+    pub members: Vec<UseMember>,
+    /// `{` and `}` of the outermost group if the members are grouped
+    pub group: Option<(usize, usize)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct UseMember {
+    /// Name of the imported item, before any `as` alias
+    pub name: String,
+    /// Name given by `as`
+    pub alias: Option<String>,
+    /// First and last token of the member, alias included
+    pub first: usize,
+    pub last: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct FunctionDecl {
+    pub name: String,
+    pub name_token: usize,
+    /// First token of the declaration, attributes and modifiers included
+    pub start: usize,
+    /// `}` closing the body, or `;` for native functions
+    pub end: usize,
+    pub is_public: bool,
+    pub has_params: bool,
+    pub return_type: Option<String>,
+    /// `{` and `}` of the body
+    pub body: Option<(usize, usize)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ConstDecl {
+    pub name: String,
+    pub name_token: usize,
+    /// `const` keyword, or the first attribute
+    pub start: usize,
+    /// `;`
+    pub end: usize,
+    pub r#type: String,
+    pub value: String,
+}
+
+/// An identifier in code, with the path qualifying it and the `()` following it if any.
+#[derive(Debug, Clone)]
+pub struct Reference {
+    pub token: usize,
+    /// First token of `a::b::` when the identifier is qualified
+    pub qualifier: Option<usize>,
+    /// `(` and `)` when the identifier is immediately called without arguments
+    pub empty_call: Option<(usize, usize)>,
+}
+
+impl Reference {
+    /// Tokens of the reference, qualifier and `()` included.
+    pub fn tokens(&self) -> Range<usize> {
+        let start = self.qualifier.unwrap_or(self.token);
+        let end = self.empty_call.map_or(self.token, |(_, close)| close);
+        start..end + 1
+    }
+}
+
+#[derive(Debug)]
+pub struct SourceFile<'a> {
+    pub source: &'a str,
+    pub tokens: Vec<Token<'a>>,
+    pub modules: Vec<Module>,
+}
+
+impl<'a> SourceFile<'a> {
+    pub fn parse(source: &'a str) -> Result<Self, CoreError> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            source,
+            tokens: &tokens,
+            position: 0,
+        };
+        let modules = parser.parse_file()?;
+        Ok(Self {
+            source,
+            tokens,
+            modules,
+        })
+    }
+
+    pub fn text(&self, token: usize) -> &'a str {
+        self.tokens[token].text
+    }
+
+    /// Byte range covered by the tokens `first..=last`.
+    pub fn span(&self, first: usize, last: usize) -> Range<usize> {
+        self.tokens[first].start..self.tokens[last].end()
+    }
+
+    /// Index of the closest token before `token` that is not whitespace or a comment.
+    pub fn previous_significant(&self, token: usize) -> Option<usize> {
+        (0..token).rev().find(|i| !self.tokens[*i].is_trivia())
+    }
+
+    /// Index of the closest token after `token` that is not whitespace or a comment.
+    pub fn next_significant(&self, token: usize) -> Option<usize> {
+        (token + 1..self.tokens.len()).find(|i| !self.tokens[*i].is_trivia())
+    }
+}
+
+struct Parser<'s, 't> {
+    source: &'s str,
+    tokens: &'t [Token<'s>],
+    position: usize,
+}
+
+impl<'s> Parser<'s, '_> {
+    fn error(&self, message: &str) -> CoreError {
+        let offset = self
+            .tokens
+            .get(self.position)
+            .map_or(self.source.len(), |token| token.start);
+        malformed(self.source, offset, message)
+    }
+
+    /// Moves to the next significant token and returns its index.
+    fn skip_trivia(&mut self) -> Option<usize> {
+        while self.position < self.tokens.len() && self.tokens[self.position].is_trivia() {
+            self.position += 1;
+        }
+        (self.position < self.tokens.len()).then_some(self.position)
+    }
+
+    fn peek(&mut self) -> Option<Token<'s>> {
+        self.skip_trivia().map(|i| self.tokens[i])
+    }
+
+    fn peek_is(&mut self, kind: TokenKind, text: &str) -> bool {
+        self.peek().is_some_and(|token| token.is(kind, text))
+    }
+
+    /// Consumes the next significant token.
+    fn bump(&mut self) -> Result<usize, CoreError> {
+        let index = self
+            .skip_trivia()
+            .ok_or_else(|| self.error("unexpected end of file"))?;
+        self.position += 1;
+        Ok(index)
+    }
+
+    fn expect(&mut self, kind: TokenKind, text: &str) -> Result<usize, CoreError> {
+        if !self.peek_is(kind, text) {
+            return Err(self.error(&format!("expected `{}`", text)));
+        }
+        self.bump()
+    }
+
+    fn expect_identifier(&mut self) -> Result<usize, CoreError> {
+        match self.peek() {
+            Some(token) if token.kind == TokenKind::Identifier => self.bump(),
+            _ => Err(self.error("expected an identifier")),
+        }
+    }
+
+    /// Consumes tokens up to the one closing the bracket at the current position.
+    fn skip_balanced(&mut self, open: &str, close: &str) -> Result<usize, CoreError> {
+        self.expect(TokenKind::Symbol, open)?;
+        let mut depth = 1;
+        loop {
+            let index = self.bump()?;
+            let token = self.tokens[index];
+            if token.is(TokenKind::Symbol, open) {
+                depth += 1;
+            } else if token.is(TokenKind::Symbol, close) {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(index);
+                }
+            }
+        }
+    }
+
+    /// Consumes `a::b::c` and returns it as written, without trivia.
+    fn parse_path(&mut self) -> Result<String, CoreError> {
+        let mut path = String::new();
+        loop {
+            match self.peek() {
+                Some(token)
+                    if matches!(token.kind, TokenKind::Identifier | TokenKind::Number)
+                        || token.is(TokenKind::Symbol, "::") =>
+                {
+                    path += token.text;
+                    self.bump()?;
+                }
+                _ => break,
+            }
+        }
+        if path.is_empty() {
+            return Err(self.error("expected a path"));
+        }
+        Ok(path)
+    }
+
+    fn skip_attributes(&mut self) -> Result<(), CoreError> {
+        while self.peek_is(TokenKind::Symbol, "#") {
+            self.bump()?;
+            self.skip_balanced("[", "]")?;
+        }
+        Ok(())
+    }
+
+    fn parse_file(&mut self) -> Result<Vec<Module>, CoreError> {
+        let mut modules = vec![];
+        while let Some(token) = self.peek() {
+            match token.text {
+                "#" => self.skip_attributes()?,
+                "module" => modules.push(self.parse_module(None)?),
+                "script" => modules.push(self.parse_module(None)?),
+                "address" => {
+                    self.bump()?;
+                    let address = self.parse_path()?;
+                    self.expect(TokenKind::Symbol, "{")?;
+                    while !self.peek_is(TokenKind::Symbol, "}") {
+                        self.skip_attributes()?;
+                        modules.push(self.parse_module(Some(&address))?);
+                    }
+                    self.bump()?;
+                }
+                "spec" => {
+                    self.skip_item()?;
+                }
+                _ => return Err(self.error("expected `module`, `script` or `address`")),
+            }
+        }
+        Ok(modules)
+    }
+
+    fn parse_module(&mut self, address: Option<&str>) -> Result<Module, CoreError> {
+        let keyword = self.bump()?;
+        let (kind, name) = match self.tokens[keyword].text {
+            "script" => (ModuleKind::Script, "script".to_string()),
+            "module" => {
+                let name = self.parse_path()?;
+                let name = match address {
+                    Some(address) => format!("{}::{}", address, name),
+                    None => name,
+                };
+                (ModuleKind::Module, name)
+            }
+            _ => return Err(self.error("expected `module` or `script`")),
+        };
+        let open_brace = self.expect(TokenKind::Symbol, "{")?;
+        let mut module = Module {
+            kind,
+            name,
+            open_brace,
+            close_brace: open_brace,
+            uses: vec![],
+            functions: vec![],
+            consts: vec![],
+            references: vec![],
+        };
+        let mut declaration_names = vec![];
+        let mut skipped_uses = vec![];
+        loop {
+            let Some(start) = self.skip_trivia() else {
+                return Err(self.error("unterminated module"));
+            };
+            if self.tokens[start].is(TokenKind::Symbol, "}") {
+                module.close_brace = self.bump()?;
+                break;
+            }
+            self.skip_attributes()?;
+            let mut is_public = false;
+            loop {
+                match self.peek().map(|token| token.text) {
+                    Some("public") => {
+                        is_public = true;
+                        self.bump()?;
+                        if self.peek_is(TokenKind::Symbol, "(") {
+                            self.skip_balanced("(", ")")?;
+                        }
+                    }
+                    Some("entry" | "native" | "inline") => {
+                        self.bump()?;
+                    }
+                    Some("friend") => {
+                        // `friend fun` is a visibility, `friend a::b;` a declaration
+                        let is_visibility = self
+                            .significant_after(self.position)
+                            .next()
+                            .is_some_and(|i| self.tokens[i].text == "fun");
+                        if !is_visibility {
+                            break;
+                        }
+                        self.bump()?;
+                    }
+                    _ => break,
+                }
+            }
+            match self.peek().map(|token| token.text) {
+                Some("use") => {
+                    let position = self.position;
+                    match self.parse_use() {
+                        Ok(use_decl) => module.uses.push(use_decl),
+                        // other forms, such as Move 2 `use fun f as T.g;`, are skipped
+                        Err(_) => {
+                            self.position = position;
+                            self.skip_item()?;
+                            skipped_uses.push(start..self.position);
+                        }
+                    }
+                }
+                Some("fun") => {
+                    let function = self.parse_function(start, is_public)?;
+                    declaration_names.push(function.name_token);
+                    module.functions.push(function);
+                }
+                Some("const") => {
+                    let constant = self.parse_const(start)?;
+                    declaration_names.push(constant.name_token);
+                    module.consts.push(constant);
+                }
+                _ => {
+                    self.skip_item()?;
+                }
+            }
+        }
+
+        let mut in_use = vec![false; module.close_brace - module.open_brace];
+        for use_decl in &module.uses {
+            for i in use_decl.start..=use_decl.end {
+                in_use[i - module.open_brace] = true;
+            }
+        }
+        for i in skipped_uses.into_iter().flatten() {
+            in_use[i - module.open_brace] = true;
+        }
+        for index in module.open_brace + 1..module.close_brace {
+            let token = self.tokens[index];
+            if token.kind != TokenKind::Identifier
+                || in_use[index - module.open_brace]
+                || declaration_names.contains(&index)
+            {
+                continue;
+            }
+            let previous = self.significant_before(index).next();
+            if previous.is_some_and(|i| self.tokens[i].is(TokenKind::Symbol, ".")) {
+                continue;
+            }
+            module.references.push(self.reference(index));
+        }
+        Ok(module)
+    }
+
+    /// Significant tokens before `index`, closest first.
+    fn significant_before(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        (0..index).rev().filter(|i| !self.tokens[*i].is_trivia())
+    }
+
+    /// Significant tokens after `index`, closest first.
+    fn significant_after(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        (index + 1..self.tokens.len()).filter(|i| !self.tokens[*i].is_trivia())
+    }
+
+    fn reference(&self, index: usize) -> Reference {
+        let mut qualifier = None;
+        let mut cursor = index;
+        loop {
+            let mut previous = self.significant_before(cursor);
+            match (previous.next(), previous.next()) {
+                (Some(separator), Some(segment))
+                    if self.tokens[separator].is(TokenKind::Symbol, "::")
+                        && matches!(
+                            self.tokens[segment].kind,
+                            TokenKind::Identifier | TokenKind::Number
+                        ) =>
+                {
+                    qualifier = Some(segment);
+                    cursor = segment;
+                }
+                _ => break,
+            }
+        }
+        let mut next = self.significant_after(index);
+        let empty_call = match (next.next(), next.next()) {
+            (Some(open), Some(close))
+                if self.tokens[open].is(TokenKind::Symbol, "(")
+                    && self.tokens[close].is(TokenKind::Symbol, ")") =>
+            {
+                Some((open, close))
+            }
+            _ => None,
+        };
+        Reference {
+            token: index,
+            qualifier,
+            empty_call,
+        }
+    }
+
+    /// Skips a module item this parser does not need to look into, e.g. a struct or a spec.
+    fn skip_item(&mut self) -> Result<(), CoreError> {
+        loop {
+            let Some(token) = self.peek() else {
+                return Err(self.error("unexpected end of file"));
+            };
+            match token.text {
+                ";" => {
+                    self.bump()?;
+                    return Ok(());
+                }
+                "{" => {
+                    self.skip_balanced("{", "}")?;
+                    return Ok(());
+                }
+                "}" => return Err(self.error("unexpected `}`")),
+                _ => {
+                    self.bump()?;
+                }
+            }
+        }
+    }
+
+    fn parse_use(&mut self) -> Result<UseDecl, CoreError> {
+        let start = self.expect(TokenKind::Identifier, "use")?;
+        let mut members = vec![];
+        let mut group = None;
+        self.parse_use_tree(&mut members, &mut group, 0)?;
+        let end = self.expect(TokenKind::Symbol, ";")?;
+        Ok(UseDecl {
+            start,
+            end,
+            members,
+            group,
+        })
+    }
+
+    fn parse_use_tree(
+        &mut self,
+        members: &mut Vec<UseMember>,
+        group: &mut Option<(usize, usize)>,
+        depth: usize,
+    ) -> Result<(), CoreError> {
+        let first = self.skip_trivia().unwrap_or(self.position);
+        let mut name = None;
+        loop {
+            let Some(token) = self.peek() else {
+                return Err(self.error("unterminated use declaration"));
+            };
+            match token.text {
+                "{" => {
+                    let open = self.bump()?;
+                    loop {
+                        if self.peek_is(TokenKind::Symbol, "}") {
+                            break;
+                        }
+                        self.parse_use_tree(members, &mut None, depth + 1)?;
+                        if self.peek_is(TokenKind::Symbol, ",") {
+                            self.bump()?;
+                        } else {
+                            break;
+                        }
+                    }
+                    let close = self.expect(TokenKind::Symbol, "}")?;
+                    if depth == 0 {
+                        *group = Some((open, close));
+                    }
+                    return Ok(());
+                }
+                "::" => {
+                    self.bump()?;
+                }
+                "as" => {
+                    self.bump()?;
+                    let last = self.expect_identifier()?;
+                    members.push(UseMember {
+                        name: name.unwrap_or_default(),
+                        alias: Some(self.tokens[last].text.to_string()),
+                        first,
+                        last,
+                    });
+                    return Ok(());
+                }
+                _ if matches!(token.kind, TokenKind::Identifier | TokenKind::Number) => {
+                    name = Some(token.text.to_string());
+                    self.bump()?;
+                }
+                _ => {
+                    let last = self
+                        .significant_before(self.position)
+                        .next()
+                        .unwrap_or(first);
+                    members.push(UseMember {
+                        name: name.ok_or_else(|| self.error("expected an identifier"))?,
+                        alias: None,
+                        first,
+                        last,
+                    });
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    fn parse_function(&mut self, start: usize, is_public: bool) -> Result<FunctionDecl, CoreError> {
+        self.expect(TokenKind::Identifier, "fun")?;
+        let name_token = self.expect_identifier()?;
+        if self.peek_is(TokenKind::Symbol, "<") {
+            self.skip_balanced("<", ">")?;
+        }
+        let params_open = self.position;
+        let params_close = self.skip_balanced("(", ")")?;
+        let has_params = self.tokens[params_open..params_close]
+            .iter()
+            .filter(|token| !token.is_trivia())
+            .count()
+            > 1;
+        let mut return_type = None;
+        if self.peek_is(TokenKind::Symbol, ":") {
+            self.bump()?;
+            let type_start = self.skip_trivia().unwrap_or(self.position);
+            let mut type_end = type_start;
+            let mut depth = 0;
+            while let Some(token) = self.peek() {
+                match token.text {
+                    "(" | "<" => depth += 1,
+                    ")" | ">" => depth -= 1,
+                    "{" | ";" | "acquires" if depth == 0 => break,
+                    _ => {}
+                }
+                type_end = self.bump()?;
+            }
+            return_type = Some(
+                self.source[self.tokens[type_start].start..self.tokens[type_end].end()].to_string(),
+            );
+        }
+        while !self.peek_is(TokenKind::Symbol, "{") && !self.peek_is(TokenKind::Symbol, ";") {
+            self.bump()?;
+        }
+        let (end, body) = if self.peek_is(TokenKind::Symbol, ";") {
+            (self.bump()?, None)
+        } else {
+            let open = self.skip_trivia().unwrap_or(self.position);
+            let close = self.skip_balanced("{", "}")?;
+            (close, Some((open, close)))
+        };
+        Ok(FunctionDecl {
+            name: self.tokens[name_token].text.to_string(),
+            name_token,
+            start,
+            end,
+            is_public,
+            has_params,
+            return_type,
+            body,
+        })
+    }
+
+    fn parse_const(&mut self, start: usize) -> Result<ConstDecl, CoreError> {
+        self.expect(TokenKind::Identifier, "const")?;
+        let name_token = self.expect_identifier()?;
+        let colon = self.expect(TokenKind::Symbol, ":")?;
+        while !self.peek_is(TokenKind::Symbol, "=") {
+            self.bump()?;
+        }
+        let equal = self.bump()?;
+        while !self.peek_is(TokenKind::Symbol, ";") {
+            self.bump()?;
+        }
+        let end = self.bump()?;
+        let text = |from: usize, to: usize| {
+            self.source[self.tokens[from].end()..self.tokens[to].start]
+                .trim()
+                .to_string()
+        };
+        Ok(ConstDecl {
+            name: self.tokens[name_token].text.to_string(),
+            name_token,
+            start,
+            end,
+            r#type: text(colon, equal),
+            value: text(equal, end),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::move_parser::{tokenize, ModuleKind, SourceFile, TokenKind};
+
+    #[test]
+    fn test_tokenize_round_trip() {
+        let source = include_str!("./test_files/sample2_input.move");
+        let tokens = tokenize(source).unwrap();
+        assert_eq!(
+            tokens.iter().map(|token| token.text).collect::<String>(),
+            source
+        );
+
+        let tokens = tokenize("/* A } */ b\"}\\\"\" // FOO()\nFOO()").unwrap();
+        let kinds: Vec<_> = tokens.iter().map(|token| token.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::BlockComment,
+                TokenKind::Whitespace,
+                TokenKind::ByteString,
+                TokenKind::Whitespace,
+                TokenKind::LineComment,
+                TokenKind::Whitespace,
+                TokenKind::Identifier,
+                TokenKind::Symbol,
+                TokenKind::Symbol,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_module() {
+        let source = r#"
+address 0x1 {
+module fri {
+    use std::vector::{length, borrow as b};
+    use verifier_addr::fri_transform::FRI_MAX_STEP_SIZE;
+
+    struct Fri has drop { size: u64 }
+
+    const LOCAL: u64 = 3;
+
+    public fun MAX_COSET_SIZE(): u256 {
+        1 << FRI_MAX_STEP_SIZE()
+    }
+
+    #[view]
+    public(friend) fun size(fri: &Fri): u64 acquires Fri {
+        let text = b"MAX_COSET_SIZE()";
+        /* MAX_COSET_SIZE() } */
+        fri.size + fri_layer::MAX_COSET_SIZE() + LOCAL
+    }
+}
+}
+script {
+    fun main() {}
+}
+"#;
+        let file = SourceFile::parse(source).unwrap();
+        assert_eq!(file.modules.len(), 2);
+        let module = &file.modules[0];
+        assert_eq!(module.name, "0x1::fri");
+        assert_eq!(file.modules[1].kind, ModuleKind::Script);
+
+        let members: Vec<_> = module.uses[0].members.iter().map(|m| &m.name).collect();
+        assert_eq!(members, vec!["length", "borrow"]);
+        assert_eq!(module.uses[0].members[1].alias.as_deref(), Some("b"));
+        assert!(module.uses[0].group.is_some());
+        assert_eq!(module.uses[1].members[0].name, "FRI_MAX_STEP_SIZE");
+        assert_eq!(module.uses[1].members[0].alias, None);
+
+        assert_eq!(module.consts[0].name, "LOCAL");
+        assert_eq!(module.consts[0].r#type, "u64");
+        assert_eq!(module.consts[0].value, "3");
+
+        let getter = &module.functions[0];
+        assert_eq!(getter.name, "MAX_COSET_SIZE");
+        assert!(getter.is_public && !getter.has_params);
+        assert_eq!(getter.return_type.as_deref(), Some("u256"));
+        let size = &module.functions[1];
+        assert!(size.is_public && size.has_params);
+        assert_eq!(size.return_type.as_deref(), Some("u64"));
+
+        let references: Vec<_> = module
+            .references
+            .iter()
+            .map(|reference| {
+                let tokens = reference.tokens();
+                file.source[file.span(tokens.start, tokens.end - 1)].to_string()
+            })
+            .filter(|text| text.chars().any(|c| c.is_ascii_uppercase()))
+            .collect();
+        assert_eq!(
+            references,
+            vec![
+                "Fri",
+                "FRI_MAX_STEP_SIZE()",
+                "Fri",
+                "Fri",
+                "fri_layer::MAX_COSET_SIZE()",
+                "LOCAL"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_use_fun() {
+        let source = "module 0x1::m {\n\
+                      \x20   use fun MAX_SIZE as Queue.max_size;\n\
+                      \x20   public use fun length as Queue.len;\n\
+                      \x20   use std::vector;\n\
+                      \x20   fun f(q: &Queue): u64 { q.max_size() + MAX_N_QUERIES }\n}\n";
+        let file = SourceFile::parse(source).unwrap();
+        let module = &file.modules[0];
+        assert_eq!(module.uses.len(), 1);
+        assert_eq!(module.uses[0].members[0].name, "vector");
+        let references: Vec<_> = module
+            .references
+            .iter()
+            .map(|reference| file.text(reference.token))
+            .collect();
+        assert!(references.contains(&"MAX_N_QUERIES"));
+        assert!(!references.contains(&"MAX_SIZE"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = SourceFile::parse("module a::b {\n    fun f() {\n}").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "malformed Move file: unterminated module at 3:2"
+        );
+        assert!(SourceFile::parse("module a::b { /* }").is_err());
+    }
+}
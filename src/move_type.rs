@@ -0,0 +1,261 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+/// Types a constant can be declared with in a Move module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(try_from = "String")]
+pub enum MoveType {
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    U256,
+    Bool,
+    Address,
+    /// Byte strings `b"..."`, hex strings `x"..."` or vectors of `u8` integers
+    VectorU8,
+    /// `vector<u16>` up to `vector<u256>`, with the bit width of their elements
+    IntegerVector(u32),
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+#[error("unsupported constant type {0}")]
+pub struct UnsupportedType(String);
+
+impl FromStr for MoveType {
+    type Err = UnsupportedType;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.split_whitespace().collect::<String>();
+        if let Some(element) = s
+            .strip_prefix("vector<")
+            .and_then(|rest| rest.strip_suffix('>'))
+        {
+            return match element.parse::<MoveType>().ok().and_then(|t| t.bits()) {
+                Some(8) => Ok(MoveType::VectorU8),
+                Some(bits) => Ok(MoveType::IntegerVector(bits)),
+                None => Err(UnsupportedType(s)),
+            };
+        }
+        Ok(match s.as_str() {
+            "u8" => MoveType::U8,
+            "u16" => MoveType::U16,
+            "u32" => MoveType::U32,
+            "u64" => MoveType::U64,
+            "u128" => MoveType::U128,
+            "u256" => MoveType::U256,
+            "bool" => MoveType::Bool,
+            "address" => MoveType::Address,
+            _ => return Err(UnsupportedType(s)),
+        })
+    }
+}
+
+impl TryFrom<String> for MoveType {
+    type Error = UnsupportedType;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for MoveType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            MoveType::U8 => "u8",
+            MoveType::U16 => "u16",
+            MoveType::U32 => "u32",
+            MoveType::U64 => "u64",
+            MoveType::U128 => "u128",
+            MoveType::U256 => "u256",
+            MoveType::Bool => "bool",
+            MoveType::Address => "address",
+            MoveType::VectorU8 => "vector<u8>",
+            MoveType::IntegerVector(bits) => return write!(f, "vector<u{}>", bits),
+        })
+    }
+}
+
+impl MoveType {
+    /// Bit width of an integer type, `None` for the other types.
+    pub fn bits(&self) -> Option<u32> {
+        match self {
+            MoveType::U8 => Some(8),
+            MoveType::U16 => Some(16),
+            MoveType::U32 => Some(32),
+            MoveType::U64 => Some(64),
+            MoveType::U128 => Some(128),
+            MoveType::U256 => Some(256),
+            _ => None,
+        }
+    }
+
+    /// Bit width of the elements of a vector of integers, `None` for the other types.
+    pub fn element_bits(&self) -> Option<u32> {
+        match self {
+            MoveType::VectorU8 => Some(8),
+            MoveType::IntegerVector(bits) => Some(*bits),
+            _ => None,
+        }
+    }
+
+    /// Checks that a non-integer value is a literal of this type, integer values and the
+    /// elements of vectors (see [`vector_elements`]) are checked while being evaluated.
+    pub fn validate_literal(&self, value: &str) -> Result<(), String> {
+        let value = value.trim();
+        match self {
+            MoveType::Bool if value == "true" || value == "false" => Ok(()),
+            MoveType::Bool => Err("expected `true` or `false`".to_string()),
+            MoveType::Address => validate_address(value),
+            MoveType::VectorU8 => validate_byte_string(value),
+            MoveType::IntegerVector(_) => {
+                Err("expected a vector such as `vector[1, 2, 3]` or `[1, 2, 3]`".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Elements of a vector written `vector[a, b]` or `[a, b]`, `None` for other values.
+/// Commas inside parentheses don't separate elements and a trailing comma is allowed.
+pub fn vector_elements(value: &str) -> Option<Vec<&str>> {
+    let value = value.trim();
+    let inner = value
+        .strip_prefix("vector")
+        .unwrap_or(value)
+        .trim_start()
+        .strip_prefix('[')?
+        .strip_suffix(']')?;
+    let mut elements = vec![];
+    let (mut depth, mut start) = (0, 0);
+    for (i, c) in inner.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                elements.push(inner[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    let last = inner[start..].trim();
+    if !last.is_empty() {
+        elements.push(last);
+    }
+    Some(elements)
+}
+
+fn validate_address(value: &str) -> Result<(), String> {
+    let Some(address) = value.strip_prefix('@') else {
+        return Err("expected an address literal such as `@0x1` or `@named_addr`".to_string());
+    };
+    if let Some(hex) = address.strip_prefix("0x") {
+        if hex.is_empty() || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("invalid hexadecimal address {}", value));
+        }
+        if hex.trim_start_matches('0').len() > 64 {
+            return Err(format!("address {} does not fit in 256 bits", value));
+        }
+        return Ok(());
+    }
+    if address.is_empty()
+        || address.starts_with(|c: char| c.is_ascii_digit())
+        || !address
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        return Err(format!("invalid named address {}", value));
+    }
+    Ok(())
+}
+
+fn validate_byte_string(value: &str) -> Result<(), String> {
+    if let Some(hex) = value
+        .strip_prefix("x\"")
+        .and_then(|rest| rest.strip_suffix('"'))
+    {
+        if hex.len() % 2 != 0 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!(
+                "invalid hex string {}, expected an even number of hexadecimal digits",
+                value
+            ));
+        }
+        return Ok(());
+    }
+    if let Some(bytes) = value
+        .strip_prefix("b\"")
+        .and_then(|rest| rest.strip_suffix('"'))
+    {
+        if !bytes.is_ascii() {
+            return Err(format!("byte string {} must be ASCII", value));
+        }
+        return validate_escapes(bytes)
+            .map_err(|reason| format!("byte string {} {}", value, reason));
+    }
+    Err("expected a byte string `b\"...\"` or a hex string `x\"...\"`".to_string())
+}
+
+/// Checks the escape sequences of the content of a byte string, Move supports `\n`, `\r`,
+/// `\t`, `\\`, `\0`, `\"` and `\xHH`.
+fn validate_escapes(bytes: &str) -> Result<(), String> {
+    let mut chars = bytes.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Err("has an unescaped `\"`".to_string()),
+            '\\' => match chars.next() {
+                Some('n' | 'r' | 't' | '\\' | '0' | '"') => {}
+                Some('x') => {
+                    let digits: String = chars.by_ref().take(2).collect();
+                    if digits.len() != 2 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+                        return Err("has an invalid `\\x` escape".to_string());
+                    }
+                }
+                Some(c) => return Err(format!("has an unknown escape `\\{}`", c)),
+                None => return Err("ends with a `\\`".to_string()),
+            },
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::move_type::{vector_elements, MoveType};
+
+    #[test]
+    fn test_parse_types() {
+        assert_eq!("vector<u8>".parse(), Ok(MoveType::VectorU8));
+        assert_eq!("vector < u64 >".parse(), Ok(MoveType::IntegerVector(64)));
+        assert_eq!(MoveType::IntegerVector(256).to_string(), "vector<u256>");
+        assert!("vector<bool>".parse::<MoveType>().is_err());
+        assert!("vector<vector<u8>>".parse::<MoveType>().is_err());
+    }
+
+    #[test]
+    fn test_validate_literals() {
+        assert!(MoveType::VectorU8
+            .validate_literal(r#"b"a\n\x41\"""#)
+            .is_ok());
+        assert!(MoveType::VectorU8.validate_literal(r#"b"\q""#).is_err());
+        assert!(MoveType::VectorU8.validate_literal(r#"b"a"b""#).is_err());
+        assert!(MoveType::Address.validate_literal("@verifier_addr").is_ok());
+        assert!(MoveType::IntegerVector(64)
+            .validate_literal("1, 2")
+            .is_err());
+    }
+
+    #[test]
+    fn test_vector_elements() {
+        assert_eq!(
+            vector_elements("vector[1, (A + 2), B,]"),
+            Some(vec!["1", "(A + 2)", "B"])
+        );
+        assert_eq!(vector_elements("[]"), Some(vec![]));
+        assert_eq!(vector_elements("x\"00\""), None);
+    }
+}
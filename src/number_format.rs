@@ -0,0 +1,138 @@
+use num_bigint::BigUint;
+use serde::Deserialize;
+
+/// Base and letter case integer constants are written in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Notation {
+    Decimal,
+    /// `0xff`
+    #[default]
+    Hex,
+    /// `0xFF`
+    UpperHex,
+}
+
+/// Formatting options of integer constants, every option left out falls back to the
+/// options of the table, then to lowercase hexadecimal.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub struct NumberFormat {
+    pub format: Option<Notation>,
+    /// Pads hexadecimal values with zeros to the width of their type
+    pub padded: Option<bool>,
+    /// Separates groups of digits with `_`, by 3 in decimal and by 4 in hexadecimal
+    pub underscores: Option<bool>,
+}
+
+impl NumberFormat {
+    /// Options of `self`, completed by the ones of `fallback`.
+    pub fn or(self, fallback: NumberFormat) -> NumberFormat {
+        NumberFormat {
+            format: self.format.or(fallback.format),
+            padded: self.padded.or(fallback.padded),
+            underscores: self.underscores.or(fallback.underscores),
+        }
+    }
+
+    /// Renders `value` as a Move literal of a `bits` wide integer type.
+    pub fn render(&self, value: &BigUint, bits: u32) -> String {
+        let notation = self.format.unwrap_or_default();
+        let mut digits = match notation {
+            Notation::Decimal => value.to_str_radix(10),
+            Notation::Hex => value.to_str_radix(16),
+            Notation::UpperHex => value.to_str_radix(16).to_uppercase(),
+        };
+        let group = match notation {
+            Notation::Decimal => 3,
+            Notation::Hex | Notation::UpperHex => {
+                let width = bits.div_ceil(4) as usize;
+                if self.padded.unwrap_or(false) && digits.len() < width {
+                    digits = "0".repeat(width - digits.len()) + &digits;
+                }
+                4
+            }
+        };
+        if self.underscores.unwrap_or(false) {
+            digits = group_digits(&digits, group);
+        }
+        match notation {
+            Notation::Decimal => digits,
+            Notation::Hex | Notation::UpperHex => format!("0x{}", digits),
+        }
+    }
+}
+
+/// Inserts `_` between groups of `size` digits, counting from the right.
+fn group_digits(digits: &str, size: usize) -> String {
+    let mut result = String::with_capacity(digits.len() + digits.len() / size);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(size) {
+            result.push('_');
+        }
+        result.push(c);
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use num_bigint::BigUint;
+
+    use crate::number_format::{Notation, NumberFormat};
+
+    #[test]
+    fn test_render() {
+        let format = |format, padded, underscores| NumberFormat {
+            format: Some(format),
+            padded: Some(padded),
+            underscores: Some(underscores),
+        };
+        let value = BigUint::from(1234567u32);
+        assert_eq!(NumberFormat::default().render(&value, 64), "0x12d687");
+        assert_eq!(
+            format(Notation::Decimal, true, false).render(&value, 64),
+            "1234567"
+        );
+        assert_eq!(
+            format(Notation::Decimal, false, true).render(&value, 64),
+            "1_234_567"
+        );
+        assert_eq!(
+            format(Notation::UpperHex, false, true).render(&BigUint::from(0xabcdefu32), 32),
+            "0xAB_CDEF"
+        );
+        assert_eq!(
+            format(Notation::Hex, true, false).render(&BigUint::from(3u8), 64),
+            "0x0000000000000003"
+        );
+        assert_eq!(
+            format(Notation::Hex, true, true).render(&BigUint::from(3u8), 32),
+            "0x0000_0003"
+        );
+        assert_eq!(
+            format(Notation::Decimal, false, true).render(&BigUint::from(100u8), 8),
+            "100"
+        );
+    }
+
+    #[test]
+    fn test_fallback() {
+        let table = NumberFormat {
+            format: Some(Notation::Decimal),
+            padded: None,
+            underscores: Some(true),
+        };
+        let constant = NumberFormat {
+            format: Some(Notation::UpperHex),
+            ..Default::default()
+        };
+        assert_eq!(
+            constant.or(table),
+            NumberFormat {
+                format: Some(Notation::UpperHex),
+                padded: None,
+                underscores: Some(true),
+            }
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::const_values::ConstantValue;
+use crate::core_error::CoreError;
+use crate::file_manager::SKIPPED_DIRS;
+use crate::move_type::MoveType;
+
+pub const MOVE_MANIFEST: &str = "Move.toml";
+/// Directories of a package holding Move files.
+pub const PACKAGE_DIRS: [&str; 3] = ["sources", "tests", "scripts"];
+
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    package: PackageSection,
+    #[serde(default)]
+    addresses: BTreeMap<String, String>,
+    #[serde(default, rename = "dev-addresses")]
+    dev_addresses: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PackageSection {
+    name: String,
+}
+
+/// A Move package, read from its `Move.toml`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MovePackage {
+    pub name: String,
+    /// Directory of the `Move.toml`
+    pub root: PathBuf,
+    /// Named addresses, `_` for the ones left unassigned
+    pub addresses: BTreeMap<String, String>,
+    pub dev_addresses: BTreeMap<String, String>,
+}
+
+impl MovePackage {
+    pub fn load(root: &Path) -> Result<Self, CoreError> {
+        let manifest_path = root.join(MOVE_MANIFEST);
+        let manifest: Manifest =
+            toml::from_str(&fs::read_to_string(&manifest_path)?).map_err(|err| {
+                CoreError::InvalidManifest {
+                    path: manifest_path.display().to_string(),
+                    err: Box::new(err),
+                }
+            })?;
+        Ok(Self {
+            name: manifest.package.name,
+            root: root.to_path_buf(),
+            addresses: manifest.addresses,
+            dev_addresses: manifest.dev_addresses,
+        })
+    }
+
+    /// Existing [`PACKAGE_DIRS`] of the package.
+    pub fn source_dirs(&self) -> Vec<PathBuf> {
+        PACKAGE_DIRS
+            .iter()
+            .map(|dir| self.root.join(dir))
+            .filter(|dir| dir.is_dir())
+            .collect()
+    }
+
+    /// Whether the package declares the named address `name`, for development or not.
+    pub fn has_address(&self, name: &str) -> bool {
+        self.addresses.contains_key(name) || self.dev_addresses.contains_key(name)
+    }
+
+    /// Named addresses of the `address` constants `keys` of `table` that the package does not
+    /// declare, with the keys of the constants. They may still come from a dependency.
+    pub fn undeclared_addresses<'a>(
+        &self,
+        table: &HashMap<String, ConstantValue>,
+        keys: impl IntoIterator<Item = &'a String>,
+    ) -> Vec<(String, String)> {
+        keys.into_iter()
+            .filter_map(|key| {
+                let constant = table.get(key)?;
+                if constant.r#type != MoveType::Address {
+                    return None;
+                }
+                let address = constant.value.trim().trim_start_matches('@');
+                if address.starts_with("0x") || self.has_address(address) {
+                    return None;
+                }
+                Some((key.clone(), address.to_string()))
+            })
+            .collect()
+    }
+}
+
+/// The package at `dir`, or every package below `dir` if it is a workspace without a
+/// `Move.toml`, sorted by path. Build directories and dependency caches are skipped.
+pub fn discover_packages(dir: &Path) -> Result<Vec<MovePackage>, CoreError> {
+    if dir.join(MOVE_MANIFEST).is_file() {
+        return Ok(vec![MovePackage::load(dir)?]);
+    }
+    let mut packages = vec![];
+    let mut entries: Vec<_> = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, _>>()?;
+    entries.sort();
+    for path in entries {
+        let is_skipped = path
+            .file_name()
+            .is_some_and(|name| SKIPPED_DIRS.iter().any(|dir| name == *dir));
+        if path.is_dir() && !is_skipped {
+            packages.extend(discover_packages(&path)?);
+        }
+    }
+    Ok(packages)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::fs;
+
+    use crate::core_error::CoreError;
+    use crate::package::{discover_packages, MOVE_MANIFEST};
+
+    #[test]
+    fn test_discover_packages() {
+        let dir = std::env::temp_dir().join("orn_test_discover_packages");
+        let _ = fs::remove_dir_all(&dir);
+        for package in ["verifier", "libs/lib", "verifier/build/deps"] {
+            fs::create_dir_all(dir.join(package).join("sources")).unwrap();
+            fs::write(
+                dir.join(package).join(MOVE_MANIFEST),
+                format!(
+                    "[package]\nname = \"{}\"\nversion = \"1.0.0\"\n\n\
+                     [addresses]\nverifier_addr = \"_\"\n\n\
+                     [dev-addresses]\nlib_addr = \"0x2\"\n",
+                    package.replace('/', "_")
+                ),
+            )
+            .unwrap();
+        }
+        fs::create_dir_all(dir.join("verifier/tests")).unwrap();
+
+        let packages = discover_packages(&dir).unwrap();
+        let names: Vec<_> = packages.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["libs_lib", "verifier"]);
+        let verifier = &packages[1];
+        assert_eq!(
+            verifier.source_dirs(),
+            vec![dir.join("verifier/sources"), dir.join("verifier/tests")]
+        );
+        assert_eq!(verifier.addresses["verifier_addr"], "_");
+        assert!(verifier.has_address("lib_addr"));
+
+        let table = toml::from_str::<HashMap<String, _>>(
+            r#"
+OWNER = { type = "address", value = "@verifier_addr" }
+ADMIN = { type = "address", value = "@0x1" }
+FRAMEWORK = { type = "address", value = "@aptos_framework" }
+OTHER = { type = "address", value = "@other_addr" }
+"#,
+        )
+        .unwrap();
+        let keys = ["OWNER", "ADMIN", "FRAMEWORK"].map(String::from);
+        // constants the package doesn't use are not checked
+        assert_eq!(
+            verifier.undeclared_addresses(&table, &keys),
+            vec![("FRAMEWORK".to_string(), "aptos_framework".to_string())]
+        );
+        assert!(matches!(
+            discover_packages(&dir.join("missing")),
+            Err(CoreError::IOError(_))
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -11,7 +11,7 @@ module verifier_addr::fri_layer {
 
     // This line is used for generating constants DO NOT REMOVE!
     // 0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF000000000000000000000000
-    const COMMITMENT_MASK: u256 = 0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF000000000000000000000000;
+    const COMMITMENT_MASK: u256 = 0xffffffffffffffffffffffffffffffffffffffff000000000000000000000000;
     // 0
     const FRI_CTX_TO_COSET_EVALUATIONS_OFFSET: u64 = 0x0;
     // FRI_GROUP_SIZE
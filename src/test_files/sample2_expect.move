@@ -7,10 +7,20 @@ module verifier_addr::stark_verifier_7 {
     use verifier_addr::memory_access_utils_7::{get_fri_step_sizes};
     use lib_addr::math_mod::{mod_mul, mod_exp};
     use verifier_addr::fact_registry::is_valid;
+    use verifier_addr::verifier_channel::{init_channel, read_hash, send_field_elements, read_field_element,
+        verify_proof_of_work, send_random_queries
+    };
+    use lib_addr::bytes::{u256_from_bytes_be, vec_to_bytes_be, num_to_bytes_be};
+    use verifier_addr::layout_specific_7::{layout_specific_init, safe_div, prepare_for_oods_check};
+    use verifier_addr::public_memory_offsets_7::{get_offset_page_size, get_public_input_length, get_offset_page_prod,
+        get_offset_page_hash, get_offset_page_addr
+    };
+    use verifier_addr::prime_field_element_0::{fpow, generator_val, k_modulus, fadd, fmul, fsub, inverse};
+    use verifier_addr::vector::{assign, set_el, append_vector};
 
     // This line is used for generating constants DO NOT REMOVE!
     // 0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF000000000000000000000000
-    const COMMITMENT_MASK: u256 = 0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF000000000000000000000000;
+    const COMMITMENT_MASK: u256 = 0xffffffffffffffffffffffffffffffffffffffff000000000000000000000000;
     // 2
     const CONSTRAINTS_DEGREE_BOUND: u64 = 0x2;
     // 1
@@ -26,27 +36,21 @@ module verifier_addr::stark_verifier_7 {
     // 1
     const INITIAL_PC: u64 = 0x1;
     // 42800643258479064999893963318903811951182475189843316
-    const LAYOUT_CODE: u256 = 42800643258479064999893963318903811951182475189843316;
+    const LAYOUT_CODE: u256 = 0x7265637572736976655f6c617267655f6f7574707574;
     // 4
     const LOG_CPU_COMPONENT_HEIGHT: u256 = 0x4;
     // 10
     const MAX_FRI_STEPS: u64 = 0xa;
     // 48
     const MAX_N_QUERIES: u64 = 0x30;
-    // 4
-    const MAX_SUPPORTED_FRI_STEP_SIZE: u64 = 0x4;
     // 1
     const MM_BLOW_UP_FACTOR: u64 = 0x1;
     // 10
     const MM_CHANNEL: u64 = 0xa;
     // 358
     const MM_COMPOSITION_ALPHA: u64 = 0x166;
-    // 551
-    const MM_COMPOSITION_OODS_VALUES: u64 = 0x227;
     // 1178
     const MM_COMPOSITION_QUERY_RESPONSES: u64 = 0x49a;
-    // 317
-    const MM_CONSTRAINT_POLY_ARGS_START: u64 = 0x13d;
     // 1277
     const MM_CONTEXT_SIZE: u64 = 0x4fd;
     // 4
@@ -137,28 +141,28 @@ module verifier_addr::stark_verifier_7 {
     const N_OODS_COEFFICIENTS: u64 = 0xc2;
     // MASK_SIZE + CONSTRAINTS_DEGREE_BOUND
     const N_OODS_VALUES: u64 = 0xc2;
-    // 6
-    const OFFSET_EXECUTION_BEGIN_ADDR: u64 = 0x6;
     // 7
-    const OFFSET_EXECUTION_STOP_PTR: u64 = 0x7;
-    // 3
-    const OFFSET_LAYOUT_CODE: u64 = 0x3;
-    // 0
-    const OFFSET_LOG_N_STEPS: u64 = 0x0;
-    // 20
-    const OFFSET_N_PUBLIC_MEMORY_PAGES: u64 = 0x14;
+    const OFFSET_EXECUTION_BEGIN_ADDR: u64 = 0x7;
+    // 8
+    const OFFSET_EXECUTION_STOP_PTR: u64 = 0x8;
     // 4
-    const OFFSET_PROGRAM_BEGIN_ADDR: u64 = 0x4;
-    // 5
-    const OFFSET_PROGRAM_STOP_PTR: u64 = 0x5;
+    const OFFSET_LAYOUT_CODE: u64 = 0x4;
+    // 1
+    const OFFSET_LOG_N_STEPS: u64 = 0x1;
     // 21
-    const OFFSET_PUBLIC_MEMORY: u64 = 0x15;
-    // 18
-    const OFFSET_PUBLIC_MEMORY_PADDING_ADDR: u64 = 0x12;
+    const OFFSET_N_PUBLIC_MEMORY_PAGES: u64 = 0x15;
+    // 5
+    const OFFSET_PROGRAM_BEGIN_ADDR: u64 = 0x5;
+    // 6
+    const OFFSET_PROGRAM_STOP_PTR: u64 = 0x6;
+    // 22
+    const OFFSET_PUBLIC_MEMORY: u64 = 0x16;
+    // 19
+    const OFFSET_PUBLIC_MEMORY_PADDING_ADDR: u64 = 0x13;
+    // 3
+    const OFFSET_RC_MAX: u64 = 0x3;
     // 2
-    const OFFSET_RC_MAX: u64 = 0x2;
-    // 1
-    const OFFSET_RC_MIN: u64 = 0x1;
+    const OFFSET_RC_MIN: u64 = 0x2;
     // 5
     const PROOF_PARAMS_FRI_STEPS_OFFSET: u64 = 0x5;
     // 4
@@ -170,16 +174,6 @@ module verifier_addr::stark_verifier_7 {
     // End of generating constants!
 
 
-    use verifier_addr::verifier_channel::{init_channel, read_hash, send_field_elements, read_field_element,
-        verify_proof_of_work, send_random_queries
-    };
-    use lib_addr::bytes::{u256_from_bytes_be, vec_to_bytes_be, num_to_bytes_be};
-    use verifier_addr::layout_specific_7::{layout_specific_init, safe_div, prepare_for_oods_check};
-    use verifier_addr::public_memory_offsets_7::{get_offset_page_size, get_public_input_length, get_offset_page_prod,
-        get_offset_page_hash, get_offset_page_addr
-    };
-    use verifier_addr::prime_field_element_0::{fpow, generator_val, k_modulus, fadd, fmul, fsub, inverse};
-    use verifier_addr::vector::{assign, set_el, append_vector};
 
     // constants
     const PROOF_PARAMS_N_QUERIES_OFFSET: u64 = 0;
@@ -842,7 +836,7 @@ module verifier_addr::stark_verifier_7 {
         // address
         // lconstraintPoly = address(constraintPoly);
         // let offset = 1 + MM_CONSTRAINT_POLY_ARGS_START();
-        // let size = MM_CONSTRAINT_POLY_ARGS_END() - MM_CONSTRAINT_POLY_ARGS_START;
+        // let size = MM_CONSTRAINT_POLY_ARGS_END() - MM_CONSTRAINT_POLY_ARGS_START();
         // assembly {
         //     // Call CpuConstraintPoly contract.
         //     let p = mload(0x40)
@@ -855,7 +849,7 @@ module verifier_addr::stark_verifier_7 {
 
         // let claimed_composition = fadd(
         //     *borrow(ctx, MM_COMPOSITION_OODS_VALUES()),
-        //     fmul(*borrow(ctx, MM_OODS_POINT()), *borrow(ctx, MM_COMPOSITION_OODS_VALUES + 1))
+        //     fmul(*borrow(ctx, MM_OODS_POINT()), *borrow(ctx, MM_COMPOSITION_OODS_VALUES() + 1))
         // );
         //
         // assert!(
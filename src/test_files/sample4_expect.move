@@ -0,0 +1,60 @@
+address verifier_addr {
+    module fri_transform {
+        use verifier_addr::prime_field_element_0::fmul;
+
+        // This line is used for generating constants DO NOT REMOVE!
+        // 4
+        const FRI_MAX_STEP_SIZE: u256 = 0x4;
+        // End of generating constants!
+
+
+
+        public fun transform_coset(step: u256): u256 {
+            assert!(step <= FRI_MAX_STEP_SIZE, 1);
+            fmul(step, 2)
+        }
+    }
+
+    module fri_layer {
+        use verifier_addr::fri_transform::{transform_coset};
+
+        // This line is used for generating constants DO NOT REMOVE!
+        // 4
+        const FRI_MAX_STEP_SIZE: u256 = 0x4;
+        // 3
+        const FRI_QUEUE_SLOT_SIZE: u64 = 0x3;
+        // End of generating constants!
+
+
+
+        public fun next_layer(step: u256): u256 {
+            transform_coset(step) + FRI_QUEUE_SLOT_SIZE * FRI_MAX_STEP_SIZE
+        }
+    }
+}
+
+module verifier_addr::merkle_verifier {
+    // This line is used for generating constants DO NOT REMOVE!
+    // 0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF000000000000000000000000
+    const COMMITMENT_MASK: u256 = 0xffffffffffffffffffffffffffffffffffffffff000000000000000000000000;
+    // End of generating constants!
+
+    public fun verify(root: u256): u256 {
+        root & COMMITMENT_MASK
+    }
+}
+
+script {
+    use verifier_addr::fri_layer::{next_layer};
+
+    // This line is used for generating constants DO NOT REMOVE!
+    // 3
+    const FRI_QUEUE_SLOT_SIZE: u64 = 0x3;
+    // End of generating constants!
+
+
+
+    fun main() {
+        assert!(next_layer(FRI_QUEUE_SLOT_SIZE) > 0, 0);
+    }
+}
@@ -0,0 +1,40 @@
+address verifier_addr {
+    module fri_transform {
+        use verifier_addr::prime_field_element_0::fmul;
+
+        public fun FRI_MAX_STEP_SIZE(): u256 {
+            4
+        }
+
+        public fun transform_coset(step: u256): u256 {
+            assert!(step <= FRI_MAX_STEP_SIZE(), 1);
+            fmul(step, 2)
+        }
+    }
+
+    module fri_layer {
+        use verifier_addr::fri_transform::{FRI_MAX_STEP_SIZE, transform_coset};
+
+        public fun FRI_QUEUE_SLOT_SIZE(): u256 {
+            3
+        }
+
+        public fun next_layer(step: u256): u256 {
+            transform_coset(step) + FRI_QUEUE_SLOT_SIZE() * FRI_MAX_STEP_SIZE()
+        }
+    }
+}
+
+module verifier_addr::merkle_verifier {
+    public fun verify(root: u256): u256 {
+        root & verifier_addr::merkle_verifier::COMMITMENT_MASK()
+    }
+}
+
+script {
+    use verifier_addr::fri_layer::{next_layer, FRI_QUEUE_SLOT_SIZE};
+
+    fun main() {
+        assert!(next_layer(FRI_QUEUE_SLOT_SIZE()) > 0, 0);
+    }
+}
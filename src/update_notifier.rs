@@ -1,7 +1,29 @@
+use std::fmt::Debug;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::{env, fs};
+
 use curl::easy::{Easy, List};
-use serde_derive::Deserialize;
+use serde_derive::{Deserialize, Serialize};
 
 pub const REGISTRY_URL: &str = "https://crates.io";
+pub const GITHUB_API_URL: &str = "https://api.github.com";
+/// Disables the update check when set to anything but `0` or an empty value.
+pub const NO_UPDATE_CHECK_ENV: &str = "ORN_NO_UPDATE_CHECK";
+/// Also notifies about pre-releases when set to anything but `0` or an empty value.
+pub const UPDATE_CHECK_PRERELEASE_ENV: &str = "ORN_UPDATE_CHECK_PRERELEASE";
+/// Hours between two update checks, 24 by default.
+pub const UPDATE_CHECK_INTERVAL_ENV: &str = "ORN_UPDATE_CHECK_INTERVAL";
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+/// The registry gets this long to answer, the command is never held up any longer.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(3);
+const USER_AGENT: &str = concat!(
+    "User-Agent: orn-cli/",
+    env!("CARGO_PKG_VERSION"),
+    " (https://github.com/sota-zk-labs/orn)"
+);
 
 #[derive(Debug, thiserror::Error)]
 enum ErrorKind {
@@ -11,9 +33,36 @@ enum ErrorKind {
     RegistryError(String),
 }
 
+/// A published version of a crate.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Release {
+    #[serde(rename = "num")]
+    pub version: String,
+    #[serde(default)]
+    pub yanked: bool,
+    /// Flagged as a pre-release by the source, whatever its version says
+    #[serde(default)]
+    pub prerelease: bool,
+}
+
+/// Where the published versions of a crate are looked up.
+pub trait VersionSource: Debug + Send + Sync {
+    /// Every version of the crate `name`, in any order.
+    fn releases(&self, name: &str) -> Result<Vec<Release>, Box<dyn std::error::Error>>;
+
+    /// Page to point users to for the details of a release.
+    fn release_url(&self, name: &str) -> String;
+}
+
+/// crates.io, or any registry serving its API.
+#[derive(Debug, Clone)]
+pub struct CratesIo {
+    pub url: String,
+}
+
 #[derive(Deserialize, Debug, Clone)]
 struct VersionResponse {
-    versions: Option<Vec<Version>>,
+    versions: Option<Vec<Release>>,
     errors: Option<Vec<JsonError>>,
 }
 
@@ -22,17 +71,20 @@ struct JsonError {
     detail: String,
 }
 
-#[derive(Deserialize, Debug, Clone)]
-struct Version {
-    num: String,
+impl VersionSource for CratesIo {
+    fn releases(&self, name: &str) -> Result<Vec<Release>, Box<dyn std::error::Error>> {
+        let url = format!("{}/api/v1/crates/{}/versions", self.url, name);
+        releases_from_json(&serde_json::from_str(&fetch(&url, &[])?)?)
+    }
+
+    fn release_url(&self, name: &str) -> String {
+        format!("{}/crates/{}", self.url, name)
+    }
 }
 
-fn get_latest_from_json(resp: &VersionResponse) -> Result<String, Box<dyn std::error::Error>> {
+fn releases_from_json(resp: &VersionResponse) -> Result<Vec<Release>, Box<dyn std::error::Error>> {
     if let Some(versions) = &resp.versions {
-        match versions.first() {
-            Some(version) => Ok(version.num.clone()),
-            None => Err(ErrorKind::UnableToParseJson("Versions array is empty".to_string()).into()),
-        }
+        Ok(versions.clone())
     } else if let Some(errors) = &resp.errors {
         match errors.first() {
             Some(error) => Err(ErrorKind::RegistryError(error.detail.clone()).into()),
@@ -48,18 +100,150 @@ fn get_latest_from_json(resp: &VersionResponse) -> Result<String, Box<dyn std::e
     }
 }
 
-fn get_latest_version(
-    crate_name: &str,
-    registry_url: &str,
-) -> Result<String, Box<dyn std::error::Error>> {
+/// The releases of a GitHub repository, tagged `v1.2.3` or `1.2.3`. Drafts are left out.
+#[derive(Debug, Clone)]
+pub struct GithubReleases {
+    /// `owner/name`
+    pub repository: String,
+    pub api_url: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct GithubRelease {
+    tag_name: String,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    prerelease: bool,
+}
+
+impl VersionSource for GithubReleases {
+    fn releases(&self, _name: &str) -> Result<Vec<Release>, Box<dyn std::error::Error>> {
+        let url = format!("{}/repos/{}/releases", self.api_url, self.repository);
+        let releases: Vec<GithubRelease> =
+            serde_json::from_str(&fetch(&url, &["Accept: application/vnd.github+json"])?)?;
+        Ok(releases
+            .into_iter()
+            .filter(|release| !release.draft)
+            .map(|release| Release {
+                version: release.tag_name.trim_start_matches('v').to_string(),
+                yanked: false,
+                prerelease: release.prerelease,
+            })
+            .collect())
+    }
+
+    fn release_url(&self, _name: &str) -> String {
+        format!("https://github.com/{}/releases", self.repository)
+    }
+}
+
+/// A JSON file in the crates.io format, `{"versions": [{"num": "1.2.3"}]}`, either local or
+/// served over HTTP by a mirror.
+#[derive(Debug, Clone)]
+pub struct Mirror {
+    /// Path, `file://` or `http(s)://` URL of the file
+    pub location: String,
+}
+
+impl VersionSource for Mirror {
+    fn releases(&self, _name: &str) -> Result<Vec<Release>, Box<dyn std::error::Error>> {
+        let content =
+            if self.location.starts_with("http://") || self.location.starts_with("https://") {
+                fetch(&self.location, &[])?
+            } else {
+                let path = self.location.trim_start_matches("file://");
+                fs::read_to_string(path)?
+            };
+        releases_from_json(&serde_json::from_str(&content)?)
+    }
+
+    fn release_url(&self, _name: &str) -> String {
+        self.location.clone()
+    }
+}
+
+/// Source of the update check in the orn config, crates.io by default:
+///
+/// ```toml
+/// [update-check]
+/// source = { type = "github", repository = "sota-zk-labs/orn" }
+/// ```
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum SourceConfig {
+    CratesIo {
+        #[serde(default = "default_registry_url")]
+        url: String,
+    },
+    Github {
+        repository: String,
+        #[serde(default = "default_github_api_url", rename = "api-url")]
+        api_url: String,
+    },
+    Mirror {
+        location: String,
+    },
+}
+
+fn default_registry_url() -> String {
+    REGISTRY_URL.to_string()
+}
+
+fn default_github_api_url() -> String {
+    GITHUB_API_URL.to_string()
+}
+
+impl Default for SourceConfig {
+    fn default() -> Self {
+        SourceConfig::CratesIo {
+            url: default_registry_url(),
+        }
+    }
+}
+
+impl SourceConfig {
+    pub fn build(&self) -> Arc<dyn VersionSource> {
+        match self.clone() {
+            SourceConfig::CratesIo { url } => Arc::new(CratesIo { url }),
+            SourceConfig::Github {
+                repository,
+                api_url,
+            } => Arc::new(GithubReleases {
+                repository,
+                api_url,
+            }),
+            SourceConfig::Mirror { location } => Arc::new(Mirror { location }),
+        }
+    }
+}
+
+/// The `[update-check]` section of the orn config, the environment variables take precedence.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct UpdateCheckConfig {
+    pub enabled: Option<bool>,
+    /// Hours between two checks
+    pub interval: Option<u64>,
+    pub prerelease: Option<bool>,
+    #[serde(default)]
+    pub source: SourceConfig,
+}
+
+/// Body of the response to a GET request to `url`.
+fn fetch(url: &str, headers: &[&str]) -> Result<String, Box<dyn std::error::Error>> {
     // We use curl-rust here to save us importing a bunch of dependencies pulled in with reqwest
     // We're okay with a blocking api since it's only one small request
     let mut easy = Easy::new();
+    easy.connect_timeout(REQUEST_TIMEOUT)?;
+    easy.timeout(REQUEST_TIMEOUT)?;
 
-    let url = format!("{}/api/v1/crates/{}/versions", registry_url, crate_name);
-    easy.url(&url)?;
+    easy.url(url)?;
     let mut list = List::new();
-    list.append("User-Agent: Update-notifier (teshaq@mozilla.com)")?;
+    list.append(USER_AGENT)?;
+    for header in headers {
+        list.append(header)?;
+    }
     easy.http_headers(list)?;
     let mut resp_buf = Vec::new();
     // Create a different lifetime for `transfer` since it
@@ -73,12 +257,52 @@ fn get_latest_version(
         })?;
         transfer.perform()?;
     }
-    let resp = std::str::from_utf8(&resp_buf)?;
-    let json_resp = serde_json::from_str(resp)?;
-    get_latest_from_json(&json_resp)
+    Ok(std::str::from_utf8(&resp_buf)?.to_string())
+}
+
+/// Highest version of `releases`, yanked versions left out and pre-releases too unless
+/// `include_prerelease` is set.
+fn latest_release(
+    releases: &[Release],
+    include_prerelease: bool,
+) -> Result<String, Box<dyn std::error::Error>> {
+    if releases.is_empty() {
+        return Err(ErrorKind::UnableToParseJson("Versions array is empty".to_string()).into());
+    }
+    releases
+        .iter()
+        .filter(|release| !release.yanked)
+        .filter(|release| include_prerelease || !release.prerelease)
+        .filter_map(|release| semver::Version::parse(&release.version).ok())
+        .filter(|version| include_prerelease || version.pre.is_empty())
+        .max()
+        .map(|version| version.to_string())
+        .ok_or_else(|| {
+            ErrorKind::UnableToParseJson("No release in the versions array".to_string()).into()
+        })
+}
+
+/// Whether `latest_version` is strictly newer than `current_version`, versions that are not
+/// valid semver never are.
+fn is_newer(current_version: &str, latest_version: &str) -> bool {
+    match (
+        semver::Version::parse(current_version),
+        semver::Version::parse(latest_version),
+    ) {
+        (Ok(current), Ok(latest)) => latest > current,
+        _ => false,
+    }
+}
+
+fn get_latest_version(
+    source: &dyn VersionSource,
+    crate_name: &str,
+    include_prerelease: bool,
+) -> Result<String, Box<dyn std::error::Error>> {
+    latest_release(&source.releases(crate_name)?, include_prerelease)
 }
 
-fn generate_notice(name: &str, current_version: &str, latest_version: &str) -> String {
+fn generate_notice(name: &str, current_version: &str, latest_version: &str, url: &str) -> String {
     let line_1 = format!(
         "A new version of {} is available! {} → {}",
         name, current_version, latest_version
@@ -87,7 +311,6 @@ fn generate_notice(name: &str, current_version: &str, latest_version: &str) -> S
     let suggestion = format!("cargo install {}", name);
     let line_2 = format!("Use `{}` to install version {}", suggestion, latest_version);
 
-    let url = format!("{}/crates/{}", REGISTRY_URL, name);
     let line_3 = format!("Check {} for more details", url);
     let mut border_line = String::from("\n───────────────────────────────────────────────────────");
     let extension = "─";
@@ -105,26 +328,227 @@ fn generate_notice(name: &str, current_version: &str, latest_version: &str) -> S
     )
 }
 
-fn print_notice(name: &str, current_version: &str, latest_version: &str) {
-    print!("{}", generate_notice(name, current_version, latest_version));
+fn print_notice(name: &str, current_version: &str, latest_version: &str, url: &str) {
+    // the standard output may be piped to other tools
+    eprint!(
+        "{}",
+        generate_notice(name, current_version, latest_version, url)
+    );
+}
+
+/// Result of the last update check, kept between runs.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct UpdateCache {
+    /// Seconds since the Unix epoch
+    checked_at: u64,
+    latest_version: Option<String>,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
 }
 
-pub fn check_latest_version(
-    name: &str,
-    current_version: &str,
-    registry_url: &str,
-) -> Result<bool, Box<dyn std::error::Error>> {
-    let latest_version = get_latest_version(name, registry_url)?;
-    if latest_version != current_version {
-        print_notice(name, current_version, &latest_version);
-        return Ok(false);
+/// `$XDG_CACHE_HOME/orn/update-check.json`, or `~/.cache/orn/update-check.json`.
+fn default_cache_path() -> Option<PathBuf> {
+    let cache_dir = env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(cache_dir.join("orn").join("update-check.json"))
+}
+
+fn is_set(name: &str) -> bool {
+    env::var(name).is_ok_and(|value| !value.is_empty() && value != "0")
+}
+
+fn hours(hours: u64) -> Duration {
+    Duration::from_secs(hours * 60 * 60)
+}
+
+/// Checks a [`VersionSource`] for a newer version at most once per interval, in the
+/// background. Failures of the check are silently ignored, they must never fail the
+/// actual command.
+#[derive(Debug, Clone)]
+pub struct UpdateNotifier {
+    name: String,
+    current_version: String,
+    source: Arc<dyn VersionSource>,
+    cache_path: Option<PathBuf>,
+    interval: Duration,
+    offline: bool,
+    prerelease: bool,
+    enabled: bool,
+}
+
+impl UpdateNotifier {
+    pub fn new(name: &str, current_version: &str, source: Arc<dyn VersionSource>) -> Self {
+        Self {
+            name: name.to_string(),
+            current_version: current_version.to_string(),
+            source,
+            cache_path: default_cache_path(),
+            interval: DEFAULT_INTERVAL,
+            offline: false,
+            prerelease: false,
+            enabled: true,
+        }
+    }
+
+    /// A notifier configured by the orn config, then by the environment, see
+    /// [`NO_UPDATE_CHECK_ENV`], [`UPDATE_CHECK_INTERVAL_ENV`] and
+    /// [`UPDATE_CHECK_PRERELEASE_ENV`].
+    pub fn from_config(name: &str, current_version: &str, config: &UpdateCheckConfig) -> Self {
+        let interval = env::var(UPDATE_CHECK_INTERVAL_ENV)
+            .ok()
+            .and_then(|hours| hours.trim().parse::<u64>().ok())
+            .or(config.interval)
+            .map_or(DEFAULT_INTERVAL, hours);
+        let mut notifier = Self::new(name, current_version, config.source.build())
+            .interval(interval)
+            .prerelease(is_set(UPDATE_CHECK_PRERELEASE_ENV) || config.prerelease == Some(true));
+        notifier.enabled = config.enabled != Some(false) && !is_set(NO_UPDATE_CHECK_ENV);
+        notifier
+    }
+
+    pub fn cache_path(mut self, cache_path: Option<PathBuf>) -> Self {
+        self.cache_path = cache_path;
+        self
+    }
+
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Also reports pre-releases newer than the current version.
+    pub fn prerelease(mut self, prerelease: bool) -> Self {
+        self.prerelease = prerelease;
+        self
+    }
+
+    /// Only reports what the last check found, without reaching the registry.
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    fn read_cache(&self) -> Option<UpdateCache> {
+        let content = fs::read_to_string(self.cache_path.as_ref()?).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    fn write_cache(&self, cache: &UpdateCache) {
+        let Some(path) = &self.cache_path else {
+            return;
+        };
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        if let Ok(content) = serde_json::to_string(cache) {
+            let _ = fs::write(path, content);
+        }
+    }
+
+    /// Starts the check, the source is only queried when the cached result is older
+    /// than the interval.
+    pub fn check(self) -> UpdateCheck {
+        if !self.enabled {
+            return UpdateCheck {
+                notifier: self,
+                cached: None,
+                request: None,
+            };
+        }
+        let cached = self.read_cache();
+        let is_fresh = cached
+            .as_ref()
+            .is_some_and(|cache| now().saturating_sub(cache.checked_at) < self.interval.as_secs());
+        let request = (!is_fresh && !self.offline).then(|| {
+            let notifier = self.clone();
+            let previous = cached
+                .as_ref()
+                .and_then(|cache| cache.latest_version.clone());
+            std::thread::spawn(move || {
+                // keep the last known version when the source can't be reached, the
+                // check time is still recorded so that offline runs don't retry each time
+                let latest_version = get_latest_version(
+                    notifier.source.as_ref(),
+                    &notifier.name,
+                    notifier.prerelease,
+                )
+                .ok()
+                .or(previous);
+                let cache = UpdateCache {
+                    checked_at: now(),
+                    latest_version,
+                };
+                notifier.write_cache(&cache);
+                cache
+            })
+        });
+        UpdateCheck {
+            notifier: self,
+            cached,
+            request,
+        }
+    }
+}
+
+/// A running update check, see [`UpdateNotifier::check`].
+#[derive(Debug)]
+pub struct UpdateCheck {
+    notifier: UpdateNotifier,
+    cached: Option<UpdateCache>,
+    request: Option<JoinHandle<UpdateCache>>,
+}
+
+impl UpdateCheck {
+    /// Latest version known, waiting for the request to the source if there is one.
+    pub fn latest_version(self) -> Option<String> {
+        let cache = match self.request {
+            Some(request) => request.join().ok(),
+            None => self.cached,
+        };
+        cache?.latest_version
+    }
+
+    /// Latest version known without waiting: the result of the request if it already
+    /// finished, the cached one otherwise.
+    fn known_version(self) -> Option<String> {
+        let cache = match self.request {
+            Some(request) if request.is_finished() => request.join().ok(),
+            _ => self.cached,
+        };
+        cache?.latest_version
+    }
+
+    /// Prints a notice on the standard error if a newer version is known. A request still
+    /// running is not waited for, it updates the cache for the next run.
+    pub fn notify(self) {
+        let notifier = self.notifier.clone();
+        if let Some(latest_version) = self.known_version() {
+            if is_newer(&notifier.current_version, &latest_version) {
+                print_notice(
+                    &notifier.name,
+                    &notifier.current_version,
+                    &latest_version,
+                    &notifier.source.release_url(&notifier.name),
+                );
+            }
+        }
     }
-    Ok(true)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    fn crates_io(server: &mockito::Server) -> CratesIo {
+        CratesIo { url: server.url() }
+    }
+
     #[test]
     fn test_latest_version() {
         let mut server = mockito::Server::new();
@@ -143,7 +567,7 @@ mod tests {
             ]}"#,
             )
             .create();
-        let latest_version = get_latest_version("asdev", &server.url()).unwrap();
+        let latest_version = get_latest_version(&crates_io(&server), "asdev", false).unwrap();
         _m.expect(1).assert();
         assert_eq!(latest_version, "0.1.3")
     }
@@ -163,8 +587,9 @@ mod tests {
                 {"errors":[{"detail":"Not Found"}]}"#,
             )
             .create();
-        let latest_version = get_latest_version("kefjhkajvcnklsajdfhwksajnceknc", &server.url())
-            .expect_err("Should be an error");
+        let latest_version =
+            get_latest_version(&crates_io(&server), "kefjhkajvcnklsajdfhwksajnceknc", false)
+                .expect_err("Should be an error");
         _m.expect(1).assert();
         assert_eq!(
             latest_version.to_string(),
@@ -190,7 +615,9 @@ mod tests {
             ]}"#,
             )
             .create();
-        check_latest_version("sameVersion", "0.1.3", &server.url()).unwrap();
+        let source = crates_io(&server);
+        let latest_version = get_latest_version(&source, "sameVersion", false).unwrap();
+        assert!(!is_newer("0.1.3", &latest_version));
         _m.expect(1).assert();
     }
 
@@ -212,13 +639,153 @@ mod tests {
             ]}"#,
             )
             .create();
-        check_latest_version("noUpdate", "0.1.2", &server.url()).unwrap();
+        let source = crates_io(&server);
+        let latest_version = get_latest_version(&source, "noUpdate", false).unwrap();
+        assert!(is_newer("0.1.2", &latest_version));
+        assert_eq!(
+            source.release_url("noUpdate"),
+            format!("{}/crates/noUpdate", server.url())
+        );
         _m.expect(1).assert();
     }
 
     #[test]
     fn test_output() {
-        assert_eq!(generate_notice("asdev", "0.1.2", "0.1.3"), "\n────────────────────────────────────────────────────────────\n\n    A new version of asdev is available! 0.1.2 → 0.1.3\n    Use `cargo install asdev` to install version 0.1.3\n    Check https://crates.io/crates/asdev for more details\n    \n────────────────────────────────────────────────────────────\n");
+        assert_eq!(generate_notice("asdev", "0.1.2", "0.1.3", "https://crates.io/crates/asdev"), "\n────────────────────────────────────────────────────────────\n\n    A new version of asdev is available! 0.1.2 → 0.1.3\n    Use `cargo install asdev` to install version 0.1.3\n    Check https://crates.io/crates/asdev for more details\n    \n────────────────────────────────────────────────────────────\n");
+    }
+
+    #[test]
+    fn test_skip_yanked_and_prerelease() {
+        let latest = |json: &str, include_prerelease| {
+            let resp: VersionResponse = serde_json::from_str(json).unwrap();
+            latest_release(&releases_from_json(&resp).unwrap(), include_prerelease)
+        };
+        let json = r#"
+            {"versions" : [
+                { "num": "0.3.0-beta.1" },
+                { "num": "0.2.1", "yanked": true },
+                { "num": "0.10.0" },
+                { "num": "0.2.0" }
+            ]}"#;
+        assert_eq!(latest(json, false).unwrap(), "0.10.0");
+        let json = r#"
+            {"versions" : [
+                { "num": "0.3.0-beta.1" },
+                { "num": "0.2.1", "yanked": true },
+                { "num": "0.2.0" }
+            ]}"#;
+        assert_eq!(latest(json, false).unwrap(), "0.2.0");
+        assert_eq!(latest(json, true).unwrap(), "0.3.0-beta.1");
+        let json = r#"{"versions" : [{ "num": "0.2.1", "yanked": true }]}"#;
+        assert!(latest(json, false).is_err());
+    }
+
+    #[test]
+    fn test_github_releases() {
+        let mut server = mockito::Server::new();
+        let _m = server
+            .mock("GET", "/repos/sota-zk-labs/orn/releases")
+            .match_header("Accept", "application/vnd.github+json")
+            .with_status(200)
+            .with_header("Content-Type", "application/json")
+            .with_body(
+                r#"[
+                { "tag_name": "v0.4.0", "draft": true, "prerelease": false },
+                { "tag_name": "v0.3.1", "draft": false, "prerelease": true },
+                { "tag_name": "v0.3.0", "draft": false, "prerelease": false }
+            ]"#,
+            )
+            .create();
+        let source = GithubReleases {
+            repository: "sota-zk-labs/orn".to_string(),
+            api_url: server.url(),
+        };
+        assert_eq!(
+            get_latest_version(&source, "orn-cli", false).unwrap(),
+            "0.3.0"
+        );
+        assert_eq!(
+            get_latest_version(&source, "orn-cli", true).unwrap(),
+            "0.3.1"
+        );
+        assert_eq!(
+            source.release_url("orn-cli"),
+            "https://github.com/sota-zk-labs/orn/releases"
+        );
+        _m.expect(2).assert();
+    }
+
+    #[test]
+    fn test_mirror() {
+        let json = r#"{"versions" : [{ "num": "0.1.3" }, { "num": "0.1.4" }]}"#;
+        let mut server = mockito::Server::new();
+        let _m = server
+            .mock("GET", "/orn-cli.json")
+            .with_status(200)
+            .with_body(json)
+            .create();
+        let source = Mirror {
+            location: format!("{}/orn-cli.json", server.url()),
+        };
+        assert_eq!(
+            get_latest_version(&source, "orn-cli", false).unwrap(),
+            "0.1.4"
+        );
+        _m.expect(1).assert();
+
+        let path = std::env::temp_dir().join("orn_test_mirror.json");
+        fs::write(&path, json).unwrap();
+        let source = Mirror {
+            location: format!("file://{}", path.display()),
+        };
+        assert_eq!(
+            get_latest_version(&source, "orn-cli", false).unwrap(),
+            "0.1.4"
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_update_check_config() {
+        let config: UpdateCheckConfig = toml::from_str(
+            r#"
+interval = 12
+source = { type = "github", repository = "sota-zk-labs/orn" }
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.interval, Some(12));
+        assert_eq!(
+            config.source,
+            SourceConfig::Github {
+                repository: "sota-zk-labs/orn".to_string(),
+                api_url: GITHUB_API_URL.to_string(),
+            }
+        );
+        let config: UpdateCheckConfig = toml::from_str("enabled = false").unwrap();
+        assert_eq!(config.source, SourceConfig::default());
+        let notifier = UpdateNotifier::from_config("orn-cli", "0.1.0", &config);
+        assert!(!notifier.enabled);
+        assert!(toml::from_str::<UpdateCheckConfig>("source = { type = \"ftp\" }").is_err());
+    }
+
+    #[test]
+    fn test_is_newer() {
+        assert!(is_newer("0.1.2", "0.1.3"));
+        assert!(is_newer("0.9.0", "0.10.0"));
+        assert!(is_newer("0.2.0-beta.1", "0.2.0"));
+        assert!(!is_newer("0.1.3", "0.1.3"));
+        // a local build ahead of the registry
+        assert!(!is_newer("0.2.0", "0.1.3"));
+        assert!(!is_newer("dev", "0.1.3"));
+    }
+
+    fn notifier(name: &str, server: &mockito::Server) -> UpdateNotifier {
+        let cache_path = std::env::temp_dir()
+            .join(format!("orn_test_update_{}", name))
+            .join("update-check.json");
+        let _ = fs::remove_file(&cache_path);
+        UpdateNotifier::new(name, "0.1.2", Arc::new(crates_io(server))).cache_path(Some(cache_path))
     }
 
     #[test]
@@ -239,7 +806,13 @@ mod tests {
             ]}"#,
             )
             .create();
-        check_latest_version("notExceeded", "0.1.2", &server.url()).unwrap();
+        let notifier = notifier("notExceeded", &server);
+        assert_eq!(
+            notifier.clone().check().latest_version(),
+            Some("0.1.3".to_string())
+        );
+        // the second run reads the cache
+        assert_eq!(notifier.check().latest_version(), Some("0.1.3".to_string()));
         _m.expect(1).assert()
     }
 
@@ -261,8 +834,88 @@ mod tests {
             ]}"#,
             )
             .create();
-
-        check_latest_version("intervalExceeded", "0.1.2", &server.url()).unwrap();
+        let notifier = notifier("intervalExceeded", &server);
+        notifier.write_cache(&UpdateCache {
+            checked_at: now() - DEFAULT_INTERVAL.as_secs() - 1,
+            latest_version: Some("0.1.2".to_string()),
+        });
+        assert_eq!(notifier.check().latest_version(), Some("0.1.3".to_string()));
         _m.expect(1).assert()
     }
+
+    #[test]
+    fn test_offline_and_unreachable() {
+        let server = mockito::Server::new();
+        let mut notifier = notifier("unreachable", &server);
+        notifier.source = Arc::new(CratesIo {
+            url: "http://127.0.0.1:9".to_string(),
+        });
+        // an unreachable registry is not an error, the check time is still recorded
+        assert_eq!(notifier.clone().check().latest_version(), None);
+        assert!(notifier.read_cache().is_some());
+
+        let notifier = notifier.interval(Duration::ZERO).offline(true);
+        notifier.write_cache(&UpdateCache {
+            checked_at: 0,
+            latest_version: Some("0.2.0".to_string()),
+        });
+        let check = notifier.clone().check();
+        assert!(check.request.is_none());
+        assert_eq!(check.latest_version(), Some("0.2.0".to_string()));
+
+        let mut disabled = notifier;
+        disabled.enabled = false;
+        assert_eq!(disabled.check().latest_version(), None);
+    }
+
+    /// Answers once `release` receives something.
+    #[derive(Debug)]
+    struct SlowSource {
+        release: std::sync::Mutex<std::sync::mpsc::Receiver<()>>,
+    }
+
+    impl VersionSource for SlowSource {
+        fn releases(&self, _name: &str) -> Result<Vec<Release>, Box<dyn std::error::Error>> {
+            self.release.lock().unwrap().recv()?;
+            Ok(vec![Release {
+                version: "0.2.0".to_string(),
+                yanked: false,
+                prerelease: false,
+            }])
+        }
+
+        fn release_url(&self, _name: &str) -> String {
+            String::new()
+        }
+    }
+
+    #[test]
+    fn test_unfinished_request() {
+        let server = mockito::Server::new();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut notifier = notifier("unfinished", &server);
+        notifier.source = Arc::new(SlowSource {
+            release: std::sync::Mutex::new(receiver),
+        });
+        notifier.write_cache(&UpdateCache {
+            checked_at: 0,
+            latest_version: Some("0.1.5".to_string()),
+        });
+        // the request is not waited for, the cached version is reported
+        assert_eq!(
+            notifier.clone().check().known_version(),
+            Some("0.1.5".to_string())
+        );
+        // and the request still updates the cache for the next run
+        sender.send(()).unwrap();
+        let mut cache = notifier.read_cache();
+        for _ in 0..100 {
+            if cache.as_ref().is_some_and(|cache| cache.checked_at != 0) {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+            cache = notifier.read_cache();
+        }
+        assert_eq!(cache.unwrap().latest_version, Some("0.2.0".to_string()));
+    }
 }